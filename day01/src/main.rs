@@ -1,27 +1,452 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::env;
-use std::fs;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let input_path = &args[1];
-    let contents = fs::read_to_string(input_path).expect("Cannot read file");
+    if args.get(1).map(String::as_str) == Some("report") {
+        let input_path = args.get(2).map(String::as_str).unwrap_or("-");
+        let format = args.get(3).map(String::as_str).unwrap_or("table");
+        let elves: Vec<Elf> = Elves::new(open_input(input_path)).map(or_exit).collect();
+        let report = Report::new(elves);
+        match format {
+            "table" => report.print_table(),
+            "csv" => report.print_csv(),
+            "json" => report.print_json(),
+            _ => {
+                eprintln!("unknown report format {:?}, use table, csv or json", format);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let input_path = args.get(1).map(String::as_str).unwrap_or("-");
+    let n: usize = match args.get(2) {
+        Some(s) => match s.parse() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                eprintln!("N must be a positive number, got {:?}", s);
+                process::exit(1);
+            }
+        },
+        None => 3,
+    };
+
+    let top = top_n(Elves::new(open_input(input_path)).map(|e| or_exit(e).total), n);
+    let terms: Vec<String> = top.iter().map(|c| c.to_string()).collect();
+    println!("{} = {}", terms.join(" + "), top.iter().map(|&c| c as i128).sum::<i128>());
+}
+
+fn or_exit<T>(result: Result<T, InventoryError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    })
+}
+
+fn open_input(path: &str) -> Box<dyn BufRead> {
+    if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(BufReader::new(File::open(path).expect("Cannot read file")))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Elf {
+    index: usize,
+    items: usize,
+    total: i64,
+}
+
+#[derive(Debug)]
+enum InventoryError {
+    Io { line: usize, source: io::Error },
+    InvalidNumber { line: usize, text: String },
+    Overflow { line: usize, text: String },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InventoryError::Io { line, source } =>
+                write!(f, "line {}: cannot read input: {}", line, source),
+            InventoryError::InvalidNumber { line, text } =>
+                write!(f, "line {}: invalid calorie count {:?}", line, text),
+            InventoryError::Overflow { line, text } =>
+                write!(f, "line {}: adding {:?} overflows the elf's total", line, text),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+/// Streams elves from a line reader, one elf at a time.
+/// Stops after the first error.
+struct Elves<R: BufRead> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    next_index: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Elves<R> {
+    fn new(reader: R) -> Elves<R> {
+        Elves { lines: reader.lines(), line_number: 0, next_index: 1, done: false }
+    }
+
+    fn fail(&mut self, error: InventoryError) -> Option<Result<Elf, InventoryError>> {
+        self.done = true;
+        Some(Err(error))
+    }
+}
+
+impl<R: BufRead> Iterator for Elves<R> {
+    type Item = Result<Elf, InventoryError>;
+
+    fn next(&mut self) -> Option<Result<Elf, InventoryError>> {
+        if self.done {
+            return None;
+        }
+
+        let mut elf = Elf { index: self.next_index, items: 0, total: 0 };
+        loop {
+            self.line_number += 1;
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(source)) => return self.fail(InventoryError::Io { line: self.line_number, source }),
+                None => {
+                    self.done = true;
+                    break;
+                }
+            };
+
+            // Lines arrive without "\n" or "\r\n"; whatever whitespace is
+            // left is noise, and a line of only whitespace separates elves.
+            let line = line.trim();
+            if line.is_empty() {
+                if elf.items > 0 {
+                    break;
+                }
+                continue;
+            }
+
+            // calorie counts cannot be negative, so a sign means corruption
+            let cals = match line.parse::<u64>() {
+                Ok(cals) => cals,
+                Err(_) => return self.fail(InventoryError::InvalidNumber { line: self.line_number, text: line.to_string() }),
+            };
+            elf.total = match i64::try_from(cals).ok().and_then(|c| elf.total.checked_add(c)) {
+                Some(total) => total,
+                None => return self.fail(InventoryError::Overflow { line: self.line_number, text: line.to_string() }),
+            };
+            elf.items += 1;
+        }
+
+        if elf.items == 0 {
+            return None;
+        }
+        self.next_index += 1;
+        Some(Ok(elf))
+    }
+}
+
+const PERCENTILES: [usize; 5] = [10, 25, 50, 75, 90];
+const HISTOGRAM_BINS: usize = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Summary statistics over all elves in an inventory.
+struct Report {
+    elves: Vec<Elf>,
+    sorted_totals: Vec<i64>,
+}
+
+impl Report {
+    fn new(elves: Vec<Elf>) -> Report {
+        let mut sorted_totals: Vec<i64> = elves.iter().map(|e| e.total).collect();
+        sorted_totals.sort();
+        Report { elves, sorted_totals }
+    }
+
+    fn mean(&self) -> Option<f64> {
+        if self.sorted_totals.is_empty() {
+            return None;
+        }
+        let sum: f64 = self.sorted_totals.iter().map(|&t| t as f64).sum();
+        Some(sum / self.sorted_totals.len() as f64)
+    }
+
+    fn median(&self) -> Option<f64> {
+        let n = self.sorted_totals.len();
+        match n {
+            0 => None,
+            _ if n % 2 == 1 => Some(self.sorted_totals[n / 2] as f64),
+            _ => Some((self.sorted_totals[n / 2 - 1] as f64 + self.sorted_totals[n / 2] as f64) / 2.0),
+        }
+    }
+
+    /// Nearest-rank percentile, `p` in 0..=100.
+    fn percentile(&self, p: usize) -> Option<i64> {
+        let n = self.sorted_totals.len();
+        if n == 0 {
+            return None;
+        }
+        let rank = (p * n).div_ceil(100).max(1);
+        Some(self.sorted_totals[rank - 1])
+    }
+
+    /// Equal-width bins as `(from, to, count)`, where `to` is inclusive.
+    fn histogram(&self) -> Vec<(i64, i64, usize)> {
+        let (min, max) = match (self.sorted_totals.first(), self.sorted_totals.last()) {
+            (Some(&min), Some(&max)) => (min as i128, max as i128),
+            _ => return vec![],
+        };
+        let width = (max - min) / HISTOGRAM_BINS as i128 + 1;
+        let mut bins: Vec<(i64, i64, usize)> = (0..HISTOGRAM_BINS as i128)
+            .map(|i| (min + i * width, min + (i + 1) * width - 1))
+            .take_while(|&(from, _)| from <= max)
+            .map(|(from, to)| (from as i64, to.min(i64::MAX as i128) as i64, 0))
+            .collect();
+        for &t in &self.sorted_totals {
+            let i = ((t as i128 - min) / width) as usize;
+            bins[i].2 += 1;
+        }
+        bins
+    }
+
+    fn print_table(&self) {
+        println!("{:>6} {:>6} {:>10}", "elf", "items", "total");
+        for e in &self.elves {
+            println!("{:>6} {:>6} {:>10}", e.index, e.items, e.total);
+        }
+        println!();
 
-    let mut cals: Vec<i32> = Vec::new();
-    let mut current_cals: i32 = 0;
+        println!("{:<8}{}", "elves:", self.elves.len());
+        if let (Some(mean), Some(median)) = (self.mean(), self.median()) {
+            println!("{:<8}{:.2}", "mean:", mean);
+            println!("{:<8}{:.1}", "median:", median);
+        }
+        for p in PERCENTILES {
+            if let Some(v) = self.percentile(p) {
+                println!("{:<8}{}", format!("p{}:", p), v);
+            }
+        }
+        println!();
+
+        let histogram = self.histogram();
+        let max_count = histogram.iter().map(|b| b.2).max().unwrap_or(0).max(1);
+        for (from, to, count) in histogram {
+            let bar = "#".repeat((count * HISTOGRAM_WIDTH).div_ceil(max_count));
+            println!("{:>10} - {:>10} | {:<w$} {}", from, to, bar, count, w = HISTOGRAM_WIDTH);
+        }
+    }
+
+    /// Three sections separated by blank lines: the elves, the summary
+    /// statistics and the histogram. Missing statistics are left empty.
+    fn print_csv(&self) {
+        println!("elf,items,total");
+        for e in &self.elves {
+            println!("{},{},{}", e.index, e.items, e.total);
+        }
+        println!();
+
+        println!("statistic,value");
+        println!("count,{}", self.elves.len());
+        println!("mean,{}", csv_or_empty(self.mean()));
+        println!("median,{}", csv_or_empty(self.median()));
+        for p in PERCENTILES {
+            println!("p{},{}", p, csv_or_empty(self.percentile(p)));
+        }
+        println!();
+
+        println!("from,to,count");
+        for (from, to, count) in self.histogram() {
+            println!("{},{},{}", from, to, count);
+        }
+    }
+
+    fn print_json(&self) {
+        let elves: Vec<String> = self.elves.iter()
+            .map(|e| format!("{{\"elf\":{},\"items\":{},\"total\":{}}}", e.index, e.items, e.total))
+            .collect();
+        let percentiles: Vec<String> = PERCENTILES.iter()
+            .map(|&p| format!("\"p{}\":{}", p, json_or_null(self.percentile(p))))
+            .collect();
+        let histogram: Vec<String> = self.histogram().iter()
+            .map(|(from, to, count)| format!("{{\"from\":{},\"to\":{},\"count\":{}}}", from, to, count))
+            .collect();
+        println!(
+            "{{\"elves\":[{}],\"count\":{},\"mean\":{},\"median\":{},\"percentiles\":{{{}}},\"histogram\":[{}]}}",
+            elves.join(","),
+            self.elves.len(),
+            json_or_null(self.mean()),
+            json_or_null(self.median()),
+            percentiles.join(","),
+            histogram.join(","),
+        );
+    }
+}
+
+fn csv_or_empty<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn json_or_null<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_else(|| "null".to_string())
+}
+
+/// Returns the `n` largest totals in descending order, or fewer if there are
+/// not that many. Only `n` values are held in memory at any time.
+fn top_n<I: IntoIterator<Item = i64>>(totals: I, n: usize) -> Vec<i64> {
+    if n == 0 {
+        return vec![];
+    }
+
+    let mut heap: BinaryHeap<Reverse<i64>> = BinaryHeap::with_capacity(n);
+
+    for total in totals {
+        if heap.len() < n {
+            heap.push(Reverse(total));
+        } else if let Some(&Reverse(smallest)) = heap.peek() {
+            if total > smallest {
+                heap.pop();
+                heap.push(Reverse(total));
+            }
+        }
+    }
+
+    heap.into_sorted_vec().into_iter().map(|Reverse(c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for line in contents.lines() {
-        if line == "" {
-            cals.push(current_cals);
-            current_cals = 0;
-        } else {
-            current_cals += line.parse::<i32>().unwrap();
+    fn sample() -> &'static str {
+        "1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000"
+    }
+
+    #[test]
+    fn test_elves() {
+        let elves: Vec<Elf> = Elves::new(sample().as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(elves, vec![
+            Elf { index: 1, items: 3, total: 6000 },
+            Elf { index: 2, items: 1, total: 4000 },
+            Elf { index: 3, items: 2, total: 11000 },
+            Elf { index: 4, items: 3, total: 24000 },
+            Elf { index: 5, items: 1, total: 10000 },
+        ]);
+    }
+
+    fn totals(input: &str) -> Vec<i64> {
+        Elves::new(input.as_bytes()).map(|e| e.unwrap().total).collect()
+    }
+
+    #[test]
+    fn test_elves_crlf() {
+        assert_eq!(totals("1000\r\n2000\r\n\r\n3000\r\n"), vec![3000, 3000]);
+    }
+
+    #[test]
+    fn test_elves_blank_runs() {
+        assert_eq!(totals("1000\n\n\n\n2000\n"), vec![1000, 2000]);
+        assert_eq!(totals("\n\n1000\n2000\n\n\n"), vec![3000]);
+        assert_eq!(totals("\n\n"), vec![]);
+        assert_eq!(totals(""), vec![]);
+    }
+
+    #[test]
+    fn test_elves_whitespace_lines() {
+        assert_eq!(totals("1000\n  \n2000\n\t\n \r\n3000"), vec![1000, 2000, 3000]);
+        assert_eq!(totals("1000  \n 2000\t\n"), vec![3000]);
+    }
+
+    #[test]
+    fn test_elves_indices_skip_separators() {
+        let elves: Vec<Elf> = Elves::new("1\n\n\n2\n".as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(elves[1], Elf { index: 2, items: 1, total: 2 });
+    }
+
+    #[test]
+    fn test_elves_errors() {
+        let mut elves = Elves::new("1000\n\n20x0\n3000".as_bytes());
+        assert!(elves.next().unwrap().is_ok());
+        match elves.next() {
+            Some(Err(InventoryError::InvalidNumber { line, text })) => {
+                assert_eq!(line, 3);
+                assert_eq!(text, "20x0");
+            }
+            e => panic!("unexpected {:?}", e),
+        }
+        assert!(elves.next().is_none());
+
+        let input = format!("{}\n1", i64::MAX);
+        let mut elves = Elves::new(input.as_bytes());
+        match elves.next() {
+            Some(Err(InventoryError::Overflow { line, .. })) => assert_eq!(line, 2),
+            e => panic!("unexpected {:?}", e),
+        }
+
+        let mut elves = Elves::new("1000
+-500
+".as_bytes());
+        match elves.next() {
+            Some(Err(InventoryError::InvalidNumber { line, text })) => {
+                assert_eq!(line, 2);
+                assert_eq!(text, "-500");
+            }
+            e => panic!("unexpected {:?}", e),
         }
     }
-    cals.push(current_cals);
-    cals.sort();
 
-    let a = cals.pop().unwrap();
-    let b = cals.pop().unwrap();
-    let c = cals.pop().unwrap();
-    println!("{} + {} + {} = {}", a, b, c, a+b+c);
+    #[test]
+    fn test_report_statistics() {
+        let report = Report::new(Elves::new(sample().as_bytes()).map(Result::unwrap).collect());
+        assert_eq!(report.mean(), Some(11000.0));
+        assert_eq!(report.median(), Some(10000.0));
+        assert_eq!(report.percentile(10), Some(4000));
+        assert_eq!(report.percentile(50), Some(10000));
+        assert_eq!(report.percentile(90), Some(24000));
+        assert_eq!(report.histogram().iter().map(|b| b.2).sum::<usize>(), 5);
+        assert_eq!(report.histogram()[0], (4000, 6000, 2));
+
+        let empty = Report::new(vec![]);
+        assert_eq!(empty.mean(), None);
+        assert_eq!(empty.median(), None);
+        assert_eq!(empty.percentile(50), None);
+        assert_eq!(empty.histogram(), vec![]);
+    }
+
+    #[test]
+    fn test_top_n() {
+        let cals = vec![6000, 4000, 11000, 24000, 10000];
+        assert_eq!(top_n(cals.clone(), 3), vec![24000, 11000, 10000]);
+        assert_eq!(top_n(cals.clone(), 1), vec![24000]);
+        assert_eq!(top_n(cals.clone(), 0), vec![]);
+        assert_eq!(top_n(cals, 10), vec![24000, 11000, 10000, 6000, 4000]);
+    }
+
+    #[test]
+    fn test_top_n_short_input() {
+        assert_eq!(top_n(vec![5, 7], 3), vec![7, 5]);
+        assert_eq!(top_n(vec![], 3), vec![]);
+    }
 }
@@ -1,83 +1,548 @@
 use std::env;
+use std::fmt;
 use std::fs;
+use std::process;
 
-#[derive(Clone)]
-enum RPS {
-    Rock,
-    Paper,
-    Scissor
+mod tournament;
+
+use tournament::Tournament;
+
+/// A move is an index into the game's list of moves.
+type Move = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+#[derive(Debug)]
+struct MoveDef {
+    name: String,
+    opponent_letter: String,
+    my_letter: String,
+    score: i32,
+}
+
+/// A cyclic game with an odd number of moves, where every move beats the
+/// (n-1)/2 moves before it in the cycle and loses to the (n-1)/2 after it.
+#[derive(Debug)]
+struct Game {
+    moves: Vec<MoveDef>,
+    outcome_letters: [String; 3],
+    outcome_scores: [i32; 3],
+}
+
+/// How the second column of the strategy guide is read.
+#[derive(Clone, Copy)]
+enum Strategy {
+    /// The letter names the move to play.
+    Shape,
+    /// The letter names the outcome to aim for.
+    Outcome,
+}
+
+#[derive(Debug, PartialEq)]
+enum GuideErrorKind {
+    MissingMove,
+    MissingGuide,
+    InvalidMove,
+    InvalidGuide,
+    UnexpectedToken,
 }
 
+/// A bad line in a strategy guide. `column` counts characters from 1 and
+/// points at the offending token, or just past the end of the line if one
+/// is missing.
+#[derive(Debug, PartialEq)]
+struct GuideError {
+    line: usize,
+    column: usize,
+    token: String,
+    kind: GuideErrorKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Goal {
+    Max,
+    Min,
+}
+
+const DEFAULT_RULES: &str = include_str!("../rules/rps.txt");
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let input_path = &args[1];
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let wins: Option<usize> = take_flag(&mut args, "--wins")
+        .map(|k| k.parse().expect("--wins needs a number"));
+    let mut rules_path = take_flag(&mut args, "--rules");
+    let skip_invalid = take_switch(&mut args, "--skip-invalid");
+    let mode = match args.first().map(String::as_str) {
+        Some(m @ ("optimise" | "tournament")) => {
+            let m = m.to_string();
+            args.remove(0);
+            m
+        }
+        _ => "score".to_string(),
+    };
+
+    if mode == "tournament" {
+        let game = load_game(rules_path.as_deref());
+        let rounds: usize = args.first().map(|r| r.parse().expect("rounds must be a number")).unwrap_or(1000);
+        let mut specs: Vec<String> = args.iter().skip(1).cloned().collect();
+        if specs.is_empty() {
+            specs = tournament::DEFAULT_PLAYERS.iter().map(|s| s.to_string()).collect();
+        }
+        let t = Tournament::run(&game, &specs, rounds).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+        t.show();
+        return;
+    }
+
+    let input_path = args.first().expect("give input file");
     let contents = fs::read_to_string(input_path).expect("Cannot read file");
+    if rules_path.is_none() {
+        rules_path = args.get(1).cloned();
+    }
+    let game = load_game(rules_path.as_deref());
+
+    if mode == "optimise" {
+        let (opponents, skipped) = game.parse_opponents(&contents, skip_invalid).unwrap_or_else(fail);
+        report_skipped("opponents", &skipped);
+        for goal in [Goal::Max, Goal::Min] {
+            match game.optimise(&opponents, goal, wins) {
+                Some((score, moves)) => println!("{:?} {}: {}", goal, score, game.guide_letters(&moves)),
+                None => println!("{:?}: cannot win exactly {} rounds", goal, wins.unwrap_or(0)),
+            }
+        }
+        return;
+    }
 
-    let mut total_score = 0;
-    for line in contents.lines() {
-        total_score += line_score(line);
+    for (name, strategy) in [("shape", Strategy::Shape), ("outcome", Strategy::Outcome)] {
+        let (score, skipped) = game.total_score(&contents, strategy, skip_invalid).unwrap_or_else(fail);
+        report_skipped(name, &skipped);
+        println!("{} strategy: {}", name, score);
     }
-    println!("{}", total_score);
 }
 
-fn line_score(line: &str) -> i32 {
-    let tokens: Vec<&str> = line.split_whitespace().collect();
-    let opponent = parse_move(&tokens[0]);
-    let guide = &tokens[1];
-    let m = my_move(&opponent, guide);
-    my_score(&m) + move_score(&opponent, &m)
+fn fail<T>(e: GuideError) -> T {
+    eprintln!("{}", e);
+    process::exit(1);
 }
 
-fn parse_move(m: &str) -> RPS {
-    match m {
-        "A" => RPS::Rock,
-        "B" => RPS::Paper,
-        "C" => RPS::Scissor,
-        _ => panic!("Invalid move")
+fn report_skipped(what: &str, skipped: &[GuideError]) {
+    for e in skipped {
+        eprintln!("{}: skipped {}", what, e);
+    }
+    if !skipped.is_empty() {
+        eprintln!("{}: skipped {} invalid lines", what, skipped.len());
+    }
+}
+
+fn load_game(rules_path: Option<&str>) -> Game {
+    let rules = match rules_path {
+        Some(path) => fs::read_to_string(path).expect("Cannot read rules file"),
+        None => DEFAULT_RULES.to_string(),
+    };
+    Game::parse(&rules).unwrap_or_else(|e| {
+        eprintln!("invalid rules: {}", e);
+        process::exit(1);
+    })
+}
+
+/// Removes the switch `name` from `args` and tells whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Removes `name` and the value following it from `args`.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    args.remove(i);
+    if i < args.len() {
+        Some(args.remove(i))
+    } else {
+        panic!("{} needs a value", name)
+    }
+}
+
+impl fmt::Display for GuideError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            GuideErrorKind::MissingMove => write!(f, "missing opponent move"),
+            GuideErrorKind::MissingGuide => write!(f, "missing guide letter"),
+            GuideErrorKind::InvalidMove => write!(f, "invalid opponent move {:?}", self.token),
+            GuideErrorKind::InvalidGuide => write!(f, "invalid guide letter {:?}", self.token),
+            GuideErrorKind::UnexpectedToken => write!(f, "unexpected {:?}", self.token),
+        }
     }
 }
 
-fn my_move(opponent: &RPS, guide: &str) -> RPS {
-    match (opponent, guide) {
-        (_, "X") => losing_move(opponent),
-        (_, "Y") => opponent.clone(),
-        (_, "Z") => winning_move(opponent),
-        _ => panic!("invalid strategy")
+impl std::error::Error for GuideError {}
+
+/// Splits a line into whitespace separated tokens with their columns,
+/// counted in characters from 1.
+fn tokens_with_columns(line: &str) -> Vec<(&str, usize)> {
+    let mut tokens = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+    for (column, (i, c)) in line.char_indices().chain([(line.len(), ' ')]).enumerate() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some((i, column + 1)),
+            (true, Some((from, token_column))) => {
+                tokens.push((&line[from..i], token_column));
+                start = None;
+            }
+            _ => (),
+        }
     }
+    tokens
 }
 
-fn losing_move(m: &RPS) -> RPS {
-    match m {
-        RPS::Rock => RPS::Scissor,
-        RPS::Paper => RPS::Rock,
-        RPS::Scissor => RPS::Paper,
+/// Runs `parse` on every line together with its 1-based number. The first
+/// bad line aborts with its error, unless `skip_invalid` is set, in which
+/// case bad lines are left out and returned alongside the results.
+fn parse_lines<T, F>(contents: &str, skip_invalid: bool, parse: F) -> Result<(Vec<T>, Vec<GuideError>), GuideError>
+    where F: Fn(&str, usize) -> Result<T, GuideError>
+{
+    let mut parsed = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        match parse(line, i + 1) {
+            Ok(x) => parsed.push(x),
+            Err(e) if skip_invalid => skipped.push(e),
+            Err(e) => return Err(e),
+        }
     }
+    Ok((parsed, skipped))
 }
 
-fn winning_move(m: &RPS) -> RPS {
-    match m {
-        RPS::Rock => RPS::Paper,
-        RPS::Paper => RPS::Scissor,
-        RPS::Scissor => RPS::Rock,
+impl Outcome {
+    fn index(self) -> usize {
+        match self {
+            Outcome::Lose => 0,
+            Outcome::Draw => 1,
+            Outcome::Win => 2,
+        }
     }
 }
 
-fn my_score(m: &RPS) -> i32 {
-    match m {
-        RPS::Rock => 1,
-        RPS::Paper => 2,
-        RPS::Scissor => 3,
+impl Game {
+    /// Parses a rules file. Blank lines and `#` comments are ignored, every
+    /// other line is one of
+    ///
+    ///     move <name> <opponent letter> <my letter> <shape score>
+    ///     lose|draw|win <letter> <outcome score>
+    ///
+    /// Moves must be listed in cycle order.
+    fn parse(rules: &str) -> Result<Game, String> {
+        let mut moves: Vec<MoveDef> = Vec::new();
+        let mut outcomes: [Option<(String, i32)>; 3] = [None, None, None];
+
+        for (i, line) in rules.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let score = |s: &str| s.parse::<i32>().map_err(|_| format!("line {}: invalid score {:?}", i + 1, s));
+            match tokens[..] {
+                ["move", name, opponent, mine, s] => moves.push(MoveDef {
+                    name: name.to_string(),
+                    opponent_letter: opponent.to_string(),
+                    my_letter: mine.to_string(),
+                    score: score(s)?,
+                }),
+                [outcome @ ("lose" | "draw" | "win"), letter, s] => {
+                    let o = match outcome {
+                        "lose" => Outcome::Lose,
+                        "draw" => Outcome::Draw,
+                        _ => Outcome::Win,
+                    };
+                    outcomes[o.index()] = Some((letter.to_string(), score(s)?));
+                }
+                _ => return Err(format!("line {}: cannot parse {:?}", i + 1, line)),
+            }
+        }
+
+        if moves.len() < 3 || moves.len().is_multiple_of(2) {
+            return Err(format!("need an odd number of at least 3 moves, got {}", moves.len()));
+        }
+        for (i, a) in moves.iter().enumerate() {
+            for b in &moves[i + 1..] {
+                if a.opponent_letter == b.opponent_letter || a.my_letter == b.my_letter {
+                    return Err(format!("moves {} and {} share a letter", a.name, b.name));
+                }
+            }
+        }
+
+        let [lose, draw, win] = outcomes;
+        match (lose, draw, win) {
+            (Some(lose), Some(draw), Some(win)) => Ok(Game {
+                moves,
+                outcome_letters: [lose.0, draw.0, win.0],
+                outcome_scores: [lose.1, draw.1, win.1],
+            }),
+            _ => Err("lose, draw and win must all be defined".to_string()),
+        }
+    }
+
+    /// Total score of a guide, and the lines skipped if `skip_invalid` is set.
+    fn total_score(&self, contents: &str, strategy: Strategy, skip_invalid: bool) -> Result<(i32, Vec<GuideError>), GuideError> {
+        let (scores, skipped) = parse_lines(contents, skip_invalid, |line, n| self.line_score(line, n, strategy))?;
+        Ok((scores.iter().sum(), skipped))
+    }
+
+    fn line_score(&self, line: &str, line_number: usize, strategy: Strategy) -> Result<i32, GuideError> {
+        let error = |column: usize, token: &str, kind: GuideErrorKind| GuideError {
+            line: line_number, column, token: token.to_string(), kind
+        };
+        let end = line.chars().count() + 1;
+
+        let tokens = tokens_with_columns(line);
+        let (opponent, guide) = match tokens[..] {
+            [] => return Err(error(end, "", GuideErrorKind::MissingMove)),
+            [_] => return Err(error(end, "", GuideErrorKind::MissingGuide)),
+            [opponent, guide] => (opponent, guide),
+            [_, _, (token, column), ..] => return Err(error(column, token, GuideErrorKind::UnexpectedToken)),
+        };
+
+        let o = self.parse_move(opponent.0)
+            .ok_or_else(|| error(opponent.1, opponent.0, GuideErrorKind::InvalidMove))?;
+        let m = match strategy {
+            Strategy::Shape => self.parse_my_move(guide.0),
+            Strategy::Outcome => self.my_move(o, guide.0),
+        }.ok_or_else(|| error(guide.1, guide.0, GuideErrorKind::InvalidGuide))?;
+        Ok(self.my_score(m) + self.move_score(o, m))
+    }
+
+    fn parse_move(&self, m: &str) -> Option<Move> {
+        self.moves.iter().position(|d| d.opponent_letter == m)
+    }
+
+    fn parse_my_move(&self, m: &str) -> Option<Move> {
+        self.moves.iter().position(|d| d.my_letter == m)
+    }
+
+    /// With more than three moves there are several ways to win or lose;
+    /// the move next to the opponent's in the cycle is picked.
+    fn my_move(&self, opponent: Move, guide: &str) -> Option<Move> {
+        match self.outcome_letters.iter().position(|l| l == guide)? {
+            0 => Some(self.losing_move(opponent)),
+            1 => Some(opponent),
+            _ => Some(self.winning_move(opponent)),
+        }
+    }
+
+    fn losing_move(&self, m: Move) -> Move {
+        (m + self.moves.len() - 1) % self.moves.len()
+    }
+
+    fn winning_move(&self, m: Move) -> Move {
+        (m + 1) % self.moves.len()
+    }
+
+    fn my_score(&self, m: Move) -> i32 {
+        self.moves[m].score
+    }
+
+    /// Outcome of playing `b` against `a`.
+    fn outcome(&self, a: Move, b: Move) -> Outcome {
+        let n = self.moves.len();
+        match (b + n - a) % n {
+            0 => Outcome::Draw,
+            d if d <= n / 2 => Outcome::Win,
+            _ => Outcome::Lose,
+        }
+    }
+
+    fn move_score(&self, a: Move, b: Move) -> i32 {
+        self.outcome_scores[self.outcome(a, b).index()]
+    }
+
+    /// Reads the opponent's moves from the first column of each line.
+    fn parse_opponents(&self, contents: &str, skip_invalid: bool) -> Result<(Vec<Move>, Vec<GuideError>), GuideError> {
+        parse_lines(contents, skip_invalid, |line, line_number| {
+            let error = |column: usize, token: &str, kind| GuideError {
+                line: line_number, column, token: token.to_string(), kind
+            };
+            match tokens_with_columns(line).first() {
+                Some(&(token, column)) => self.parse_move(token)
+                    .ok_or_else(|| error(column, token, GuideErrorKind::InvalidMove)),
+                None => Err(error(line.chars().count() + 1, "", GuideErrorKind::MissingMove)),
+            }
+        })
+    }
+
+    fn guide_letters(&self, moves: &[Move]) -> String {
+        moves.iter().map(|&m| self.moves[m].my_letter.as_str()).collect::<Vec<&str>>().join(" ")
+    }
+
+    /// Finds the moves with the highest or lowest total against `opponents`.
+    /// With `wins` set, exactly that many rounds must be won, and `None` is
+    /// returned if that is impossible.
+    fn optimise(&self, opponents: &[Move], goal: Goal, wins: Option<usize>) -> Option<(i32, Vec<Move>)> {
+        let better = |a: i32, b: i32| match goal {
+            Goal::Max => a > b,
+            Goal::Min => a < b,
+        };
+        let round = |o: Move, m: Move| (self.my_score(m) + self.move_score(o, m), self.outcome(o, m) == Outcome::Win);
+
+        let k = match wins {
+            Some(k) if k > opponents.len() => return None,
+            Some(k) => k,
+            None => {
+                let mut total = 0;
+                let mut moves = Vec::with_capacity(opponents.len());
+                for &o in opponents {
+                    let mut best: Option<(i32, Move)> = None;
+                    for m in 0..self.moves.len() {
+                        let (score, _) = round(o, m);
+                        if best.is_none_or(|(b, _)| better(score, b)) {
+                            best = Some((score, m));
+                        }
+                    }
+                    let (score, m) = best?;
+                    total += score;
+                    moves.push(m);
+                }
+                return Some((total, moves));
+            }
+        };
+
+        // best[w] is the best total over the rounds so far with exactly w
+        // wins; choices[i][w] is the move that got there in round i.
+        let mut best: Vec<Option<i32>> = vec![None; k + 1];
+        best[0] = Some(0);
+        let mut choices: Vec<Vec<Move>> = Vec::with_capacity(opponents.len());
+        for &o in opponents {
+            let mut next: Vec<Option<i32>> = vec![None; k + 1];
+            let mut chosen: Vec<Move> = vec![0; k + 1];
+            for (w, total) in best.iter().enumerate() {
+                let Some(total) = total else { continue };
+                for m in 0..self.moves.len() {
+                    let (score, won) = round(o, m);
+                    let w = w + won as usize;
+                    if w > k {
+                        continue;
+                    }
+                    if next[w].is_none_or(|b| better(total + score, b)) {
+                        next[w] = Some(total + score);
+                        chosen[w] = m;
+                    }
+                }
+            }
+            best = next;
+            choices.push(chosen);
+        }
+
+        let total = best[k]?;
+        let mut moves = vec![0; opponents.len()];
+        let mut w = k;
+        for i in (0..opponents.len()).rev() {
+            moves[i] = choices[i][w];
+            if self.outcome(opponents[i], moves[i]) == Outcome::Win {
+                w -= 1;
+            }
+        }
+        Some((total, moves))
     }
 }
 
-fn move_score(a: &RPS, b: &RPS) -> i32 {
-    match (a, b) {
-        (RPS::Rock, RPS::Rock)
-            | (RPS::Paper, RPS::Paper)
-            | (RPS::Scissor, RPS::Scissor) => 3,
-        (RPS::Rock, RPS::Paper)
-            | (RPS::Paper, RPS::Scissor)
-            | (RPS::Scissor, RPS::Rock) => 6,
-        _ => 0,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> &'static str {
+        "A Y
+B X
+C Z"
+    }
+
+    #[test]
+    fn test_total_score() {
+        let game = Game::parse(DEFAULT_RULES).unwrap();
+        assert_eq!(game.total_score(sample(), Strategy::Shape, false), Ok((15, vec![])));
+        assert_eq!(game.total_score(sample(), Strategy::Outcome, false), Ok((12, vec![])));
+    }
+
+    #[test]
+    fn test_rpsls_outcomes() {
+        let game = Game::parse(include_str!("../rules/rpsls.txt")).unwrap();
+        let m = |name: &str| game.moves.iter().position(|d| d.name == name).unwrap();
+        assert_eq!(game.outcome(m("Rock"), m("Paper")), Outcome::Win);
+        assert_eq!(game.outcome(m("Rock"), m("Spock")), Outcome::Win);
+        assert_eq!(game.outcome(m("Rock"), m("Lizard")), Outcome::Lose);
+        assert_eq!(game.outcome(m("Rock"), m("Scissors")), Outcome::Lose);
+        assert_eq!(game.outcome(m("Spock"), m("Lizard")), Outcome::Win);
+        assert_eq!(game.outcome(m("Lizard"), m("Lizard")), Outcome::Draw);
+        assert_eq!(game.line_score("A V", 1, Strategy::Shape), Ok(1 + 3));
+        assert_eq!(game.line_score("A Z", 1, Strategy::Outcome), Ok(2 + 6));
+    }
+
+    #[test]
+    fn test_optimise() {
+        let game = Game::parse(DEFAULT_RULES).unwrap();
+        let (opponents, _) = game.parse_opponents(sample(), false).unwrap();
+
+        let (max, moves) = game.optimise(&opponents, Goal::Max, None).unwrap();
+        assert_eq!((max, game.guide_letters(&moves).as_str()), (8 + 9 + 7, "Y Z X"));
+        let (min, moves) = game.optimise(&opponents, Goal::Min, None).unwrap();
+        assert_eq!((min, game.guide_letters(&moves).as_str()), (3 + 1 + 2, "Z X Y"));
+
+        let (score, moves) = game.optimise(&opponents, Goal::Max, Some(1)).unwrap();
+        assert_eq!(score, 8 + 5 + 6);
+        assert_eq!(moves.iter().zip(&opponents).filter(|(&m, &o)| game.outcome(o, m) == Outcome::Win).count(), 1);
+        let (score, _) = game.optimise(&opponents, Goal::Min, Some(3)).unwrap();
+        assert_eq!(score, 8 + 9 + 7);
+        assert_eq!(game.optimise(&opponents, Goal::Max, Some(4)), None);
+    }
+
+    #[test]
+    fn test_guide_errors() {
+        let game = Game::parse(DEFAULT_RULES).unwrap();
+        let error = |line, column, token: &str, kind| GuideError { line, column, token: token.to_string(), kind };
+        let score = |line| game.line_score(line, 7, Strategy::Outcome);
+
+        assert_eq!(score(""), Err(error(7, 1, "", GuideErrorKind::MissingMove)));
+        assert_eq!(score("A"), Err(error(7, 2, "", GuideErrorKind::MissingGuide)));
+        assert_eq!(score("  Q Y"), Err(error(7, 3, "Q", GuideErrorKind::InvalidMove)));
+        assert_eq!(score("A  W"), Err(error(7, 4, "W", GuideErrorKind::InvalidGuide)));
+        assert_eq!(score("A Y Z"), Err(error(7, 5, "Z", GuideErrorKind::UnexpectedToken)));
+        assert_eq!(score("A Y").unwrap(), 4);
+        assert_eq!(score("Ä Y"), Err(error(7, 1, "Ä", GuideErrorKind::InvalidMove)));
+        assert_eq!(score("A\u{a0}Y Ü"), Err(error(7, 5, "Ü", GuideErrorKind::UnexpectedToken)));
+        assert_eq!(score("A Ü"), Err(error(7, 3, "Ü", GuideErrorKind::InvalidGuide)));
+        assert_eq!(score("A\u{a0}"), Err(error(7, 3, "", GuideErrorKind::MissingGuide)));
+    }
+
+    #[test]
+    fn test_skip_invalid_lines() {
+        let game = Game::parse(DEFAULT_RULES).unwrap();
+        let guide = "A Y\n\nB X\nC\nC Z";
+
+        let e = game.total_score(guide, Strategy::Shape, false).unwrap_err();
+        assert_eq!((e.line, e.kind), (2, GuideErrorKind::MissingMove));
+
+        let (score, skipped) = game.total_score(guide, Strategy::Shape, true).unwrap();
+        assert_eq!(score, 15);
+        assert_eq!(skipped.iter().map(|e| e.line).collect::<Vec<usize>>(), vec![2, 4]);
+        assert_eq!(skipped[1].to_string(), "line 4, column 2: missing guide letter");
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(Game::parse("move Rock A X 1\nmove Paper B Y 2\nlose X 0\ndraw Y 3\nwin Z 6").is_err());
+        assert!(Game::parse("move Rock A X 1\nmove Paper B Y 2\nmove Scissors C Z 3").is_err());
+        assert!(Game::parse("move Rock A X one").is_err());
     }
 }
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::{Game, Move, Outcome};
+
+/// A strategy that picks a move every round and gets to see what the
+/// opponent played afterwards.
+pub trait Player {
+    fn play(&mut self, game: &Game) -> Move;
+    fn observe(&mut self, _opponent: Move) {}
+}
+
+/// Always plays the same move.
+struct Constant(Move);
+
+impl Player for Constant {
+    fn play(&mut self, _game: &Game) -> Move {
+        self.0
+    }
+}
+
+/// Plays every move in turn.
+struct Cycle(Move);
+
+impl Player for Cycle {
+    fn play(&mut self, game: &Game) -> Move {
+        let m = self.0;
+        self.0 = (self.0 + 1) % game.moves.len();
+        m
+    }
+}
+
+/// Picks moves uniformly at random from a seeded xorshift generator, so
+/// tournaments are reproducible.
+struct Random(u64);
+
+impl Player for Random {
+    fn play(&mut self, game: &Game) -> Move {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % game.moves.len() as u64) as Move
+    }
+}
+
+/// Plays whatever beats the opponent's most frequent move so far.
+struct Frequency(HashMap<Move, usize>);
+
+impl Player for Frequency {
+    fn play(&mut self, game: &Game) -> Move {
+        let most_frequent = self.0.iter()
+            .max_by_key(|&(m, count)| (count, std::cmp::Reverse(m)))
+            .map(|(&m, _)| m)
+            .unwrap_or(0);
+        game.winning_move(most_frequent)
+    }
+
+    fn observe(&mut self, opponent: Move) {
+        *self.0.entry(opponent).or_insert(0) += 1;
+    }
+}
+
+/// Plays the opponent's previous move.
+struct Mirror(Move);
+
+impl Player for Mirror {
+    fn play(&mut self, _game: &Game) -> Move {
+        self.0
+    }
+
+    fn observe(&mut self, opponent: Move) {
+        self.0 = opponent;
+    }
+}
+
+pub const DEFAULT_PLAYERS: [&str; 5] = ["constant", "cycle", "random:1", "frequency", "mirror"];
+
+/// Builds a fresh player from a spec such as `constant:Rock`, `cycle`,
+/// `random:42`, `frequency` or `mirror`.
+pub fn new_player(spec: &str, game: &Game) -> Result<Box<dyn Player>, String> {
+    let (kind, arg) = match spec.split_once(':') {
+        Some((kind, arg)) => (kind, Some(arg)),
+        None => (spec, None),
+    };
+    let move_named = |name: Option<&str>| match name {
+        None => Ok(0),
+        Some(name) => game.moves.iter().position(|d| d.name == name)
+            .ok_or(format!("unknown move {:?} in {:?}", name, spec)),
+    };
+
+    match kind {
+        "constant" => Ok(Box::new(Constant(move_named(arg)?))),
+        "cycle" => Ok(Box::new(Cycle(move_named(arg)?))),
+        "random" => {
+            let seed: u64 = arg.unwrap_or("1").parse().map_err(|_| format!("invalid seed in {:?}", spec))?;
+            // xorshift gets stuck on zero
+            Ok(Box::new(Random(seed.max(1))))
+        }
+        "frequency" => Ok(Box::new(Frequency(HashMap::new()))),
+        "mirror" => Ok(Box::new(Mirror(move_named(arg)?))),
+        _ => Err(format!("unknown player {:?}", spec)),
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Record {
+    pub points: i64,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Record {
+    fn add(&mut self, score: i32, outcome: Outcome) {
+        self.points += score as i64;
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Lose => self.losses += 1,
+        }
+    }
+
+    fn win_rate(&self) -> f64 {
+        let rounds = self.wins + self.draws + self.losses;
+        if rounds == 0 {
+            0.0
+        } else {
+            self.wins as f64 / rounds as f64
+        }
+    }
+}
+
+pub struct Tournament {
+    pub players: Vec<String>,
+    /// `pairs[i][j]` is player i's record against player j.
+    pub pairs: Vec<Vec<Record>>,
+}
+
+impl Tournament {
+    /// Plays every pair of players, including each against itself, for
+    /// `rounds` rounds with fresh players per match.
+    pub fn run(game: &Game, specs: &[String], rounds: usize) -> Result<Tournament, String> {
+        let n = specs.len();
+        let mut pairs = vec![vec![Record::default(); n]; n];
+
+        for i in 0..n {
+            for j in i..n {
+                let mut a = new_player(&specs[i], game)?;
+                let mut b = new_player(&specs[j], game)?;
+                let (mut record_a, mut record_b) = (Record::default(), Record::default());
+                for _ in 0..rounds {
+                    let (ma, mb) = (a.play(game), b.play(game));
+                    a.observe(mb);
+                    b.observe(ma);
+                    record_a.add(game.my_score(ma) + game.move_score(mb, ma), game.outcome(mb, ma));
+                    record_b.add(game.my_score(mb) + game.move_score(ma, mb), game.outcome(ma, mb));
+                }
+                pairs[i][j] = record_a;
+                if i != j {
+                    pairs[j][i] = record_b;
+                }
+            }
+        }
+
+        Ok(Tournament { players: specs.to_vec(), pairs })
+    }
+
+    /// Each player's combined record against everyone else, best first.
+    pub fn leaderboard(&self) -> Vec<(&str, Record)> {
+        let mut board: Vec<(&str, Record)> = self.players.iter().enumerate()
+            .map(|(i, name)| {
+                let mut total = Record::default();
+                for (j, r) in self.pairs[i].iter().enumerate() {
+                    if i != j {
+                        total.points += r.points;
+                        total.wins += r.wins;
+                        total.draws += r.draws;
+                        total.losses += r.losses;
+                    }
+                }
+                (name.as_str(), total)
+            })
+            .collect();
+        board.sort_by(|a, b| b.1.points.cmp(&a.1.points).then(a.0.cmp(b.0)));
+        board
+    }
+
+    pub fn show(&self) {
+        let width = self.players.iter().map(|p| p.len()).max().unwrap_or(0).max(6);
+
+        println!("{:>w$} {:>10} {:>6} {:>6} {:>6}", "player", "points", "wins", "draws", "losses", w = width);
+        for (name, r) in self.leaderboard() {
+            println!("{:>w$} {:>10} {:>6} {:>6} {:>6}", name, r.points, r.wins, r.draws, r.losses, w = width);
+        }
+        println!();
+
+        println!("win rate of row against column:");
+        print!("{:>w$}", "", w = width);
+        for name in &self.players {
+            print!(" {:>w$}", name, w = width);
+        }
+        println!();
+        for (i, name) in self.players.iter().enumerate() {
+            print!("{:>w$}", name, w = width);
+            for r in &self.pairs[i] {
+                print!(" {:>w$.1}%", r.win_rate() * 100.0, w = width - 1);
+            }
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_RULES;
+
+    #[test]
+    fn test_tournament() {
+        let game = Game::parse(DEFAULT_RULES).unwrap();
+        let specs: Vec<String> = vec!["constant:Rock".to_string(), "constant:Paper".to_string(), "mirror".to_string()];
+        let t = Tournament::run(&game, &specs, 10).unwrap();
+
+        assert_eq!(t.pairs[1][0], Record { points: 10 * (2 + 6), wins: 10, draws: 0, losses: 0 });
+        assert_eq!(t.pairs[0][1], Record { points: 10, wins: 0, draws: 0, losses: 10 });
+        assert_eq!(t.pairs[0][0].draws, 10);
+        // the mirror opens with Rock, then copies Paper and draws from then on
+        assert_eq!(t.pairs[2][1], Record { points: 1 + 9 * (2 + 3), wins: 0, draws: 9, losses: 1 });
+        assert_eq!(t.leaderboard()[0].0, "constant:Paper");
+    }
+
+    #[test]
+    fn test_random_is_reproducible() {
+        let game = Game::parse(DEFAULT_RULES).unwrap();
+        let moves = |seed: &str| {
+            let mut p = new_player(seed, &game).unwrap();
+            (0..20).map(|_| p.play(&game)).collect::<Vec<Move>>()
+        };
+        assert_eq!(moves("random:7"), moves("random:7"));
+        assert_ne!(moves("random:7"), moves("random:8"));
+        assert!(new_player("random:x", &game).is_err());
+        assert!(new_player("constant:Banana", &game).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::{group_prio, item_priority, line_prio};
+
+/// Times the `ItemSet` priorities against the earlier `HashSet` version on
+/// `lines` generated rucksacks. Build with `--release` for useful numbers.
+pub fn run(lines: usize) {
+    let rucksacks = generate(lines - lines % 3);
+    println!("{} rucksacks", rucksacks.len());
+
+    let (hashset, hashset_time) = time(|| total(&rucksacks, hashset_line_prio, hashset_group_prio));
+    let (itemset, itemset_time) = time(|| total(&rucksacks, line_prio, group_prio));
+    assert_eq!(hashset, itemset);
+
+    println!("HashSet: {:>8.1} ms", hashset_time.as_secs_f64() * 1000.0);
+    println!("ItemSet: {:>8.1} ms", itemset_time.as_secs_f64() * 1000.0);
+    println!("speed-up: {:.1}x", hashset_time.as_secs_f64() / itemset_time.as_secs_f64());
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = black_box(f());
+    (result, start.elapsed())
+}
+
+fn total(rucksacks: &[String], line: fn(&[u8]) -> i32, group: fn(&[String]) -> i32) -> (i32, i32) {
+    let line_total = rucksacks.iter().map(|r| line(black_box(r.as_bytes()))).fold(0, i32::wrapping_add);
+    let group_total = rucksacks.chunks(3).map(|g| group(black_box(g))).fold(0, i32::wrapping_add);
+    (line_total, group_total)
+}
+
+/// Rucksacks of 16 to 48 random letters from a fixed-seed xorshift generator.
+fn generate(count: usize) -> Vec<String> {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let mut state: u64 = 0x2022_1203;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..count).map(|_| {
+        let len = 16 + 2 * (next() % 17) as usize;
+        (0..len).map(|_| LETTERS[(next() % 52) as usize] as char).collect()
+    }).collect()
+}
+
+fn hashset_line_prio(line: &[u8]) -> i32 {
+    let a: HashSet<&u8> = HashSet::from_iter(&line[..line.len()/2]);
+    let b: HashSet<&u8> = HashSet::from_iter(&line[line.len()/2..]);
+    let common = a.intersection(&b);
+    common.into_iter().map(|c| item_priority(**c).unwrap_or(0)).reduce(|a, i| a + i).unwrap_or(0)
+}
+
+fn hashset_group_prio(group: &[String]) -> i32 {
+    let a: HashSet<&u8> = HashSet::from_iter(group[0].as_bytes());
+    let b: HashSet<&u8> = HashSet::from_iter(group[1].as_bytes());
+    let c: HashSet<&u8> = HashSet::from_iter(group[2].as_bytes());
+    let common = a.intersection(&b);
+
+    let mut res = 0;
+    for x in c.iter() {
+        if common.clone().any(|q| q == x) {
+            res += item_priority(**x).unwrap_or(0);
+        }
+    }
+    res
+}
@@ -1,12 +1,41 @@
-use std::{env, fs, str};
-use std::collections::HashSet;
+use std::{env, fmt, fs, process};
 
+mod bench;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let strict = take_switch(&mut args, "--strict");
+    let json = take_switch(&mut args, "--json");
+    let explain_mode = args[1] == "explain";
+    if explain_mode {
+        args.remove(1);
+    }
+    if args[1] == "bench" {
+        let lines: usize = args.get(2).map(|n| n.parse().expect("line count must be a number")).unwrap_or(3_000_000);
+        bench::run(lines);
+        return;
+    }
+
     let input_path = &args[1];
+    let group_size: usize = args.get(2).map(|n| n.parse().expect("group size must be a number")).unwrap_or(3);
+    assert!(group_size > 0, "group size must be at least 1");
     let contents = fs::read_to_string(input_path).expect("Error reading file");
 
+    let errors = validate(&contents);
+    if !errors.is_empty() {
+        for e in &errors {
+            eprintln!("error: {}", e);
+        }
+        eprintln!("{} invalid rucksacks", errors.len());
+        process::exit(1);
+    }
+
+    if explain_mode {
+        check_last_group(contents.lines().count() % group_size, group_size, strict);
+        explain(&contents, group_size, json);
+        return;
+    }
+
     let mut total_line_prio = 0;
     let mut total_group_prio = 0;
     let mut group_lines: Vec<String> = Vec::new();
@@ -14,44 +43,337 @@ fn main() {
         total_line_prio += line_prio(line.as_bytes());
 
         group_lines.push(line.to_string());
-        if group_lines.len() >= 3 {
+        if group_lines.len() >= group_size {
             total_group_prio += group_prio(&group_lines);
             group_lines.clear();
         }
     }
+
+    check_last_group(group_lines.len(), group_size, strict);
+
     println!("total_line_prio: {}", total_line_prio);
     println!("total_group_prio: {}", total_group_prio);
 }
 
-fn line_prio(line: &[u8]) -> i32 {
-    assert!(line.len() % 2 == 0);
-    let a: HashSet<&u8> = HashSet::from_iter(&line[..line.len()/2]);
-    let b: HashSet<&u8> = HashSet::from_iter(&line[line.len()/2..]);
-    let common = a.intersection(&b);
-    common.into_iter().map(|c| item_priority(**c)).reduce(|a, i| a + i).unwrap_or(0)
+/// Warns about an incomplete last group of `leftover` rucksacks, which is
+/// never counted, or exits if `strict` is set.
+fn check_last_group(leftover: usize, group_size: usize, strict: bool) {
+    if leftover == 0 {
+        return;
+    }
+    let message = format!("last group has only {} of {} rucksacks and was not counted", leftover, group_size);
+    if strict {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+    eprintln!("warning: {}", message);
+}
+
+#[derive(Debug, PartialEq)]
+enum RucksackError {
+    Empty { line: usize },
+    OddLength { line: usize, length: usize },
+    /// `column` counts characters from 1.
+    InvalidItem { line: usize, column: usize, item: char },
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RucksackError::Empty { line } =>
+                write!(f, "line {}: empty rucksack", line),
+            RucksackError::OddLength { line, length } =>
+                write!(f, "line {}: {} items cannot be split into two compartments", line, length),
+            RucksackError::InvalidItem { line, column, item } =>
+                write!(f, "line {}, column {}: {:?} is not an item", line, column, item),
+        }
+    }
+}
+
+impl std::error::Error for RucksackError {}
+
+fn validate_rucksack(line: &str, line_number: usize) -> Result<(), RucksackError> {
+    if line.is_empty() {
+        return Err(RucksackError::Empty { line: line_number });
+    }
+    if let Some((i, item)) = line.chars().enumerate().find(|(_, c)| !c.is_ascii_alphabetic()) {
+        return Err(RucksackError::InvalidItem { line: line_number, column: i + 1, item });
+    }
+    if !line.len().is_multiple_of(2) {
+        return Err(RucksackError::OddLength { line: line_number, length: line.len() });
+    }
+    Ok(())
+}
+
+/// Checks every rucksack before anything is scored, and returns the first
+/// problem found on each bad line.
+fn validate(contents: &str) -> Vec<RucksackError> {
+    contents.lines().enumerate()
+        .filter_map(|(i, line)| validate_rucksack(line, i + 1).err())
+        .collect()
+}
+
+/// Removes the switch `name` from `args` and tells whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Prints every rucksack's compartments and shared items, and every group's
+/// badge, either as a table or as JSON lines. An incomplete last group is
+/// listed without a badge, as it is not counted.
+fn explain(contents: &str, group_size: usize, json: bool) {
+    let lines: Vec<&str> = contents.lines().collect();
+    let width = lines.iter().map(|l| l.len() / 2).max().unwrap_or(0).max(6);
+    if !json {
+        println!("{:>6}  {:<w$}  {:<w$}  shared", "line", "first", "second", w = width);
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        let (first, second) = line.as_bytes().split_at(line.len() / 2);
+        let shared = ItemSet::from_items(first).intersection(&ItemSet::from_items(second));
+        let (first, second) = (String::from_utf8_lossy(first), String::from_utf8_lossy(second));
+        if json {
+            println!(
+                "{{\"type\":\"rucksack\",\"line\":{},\"first\":{},\"second\":{},\"shared\":{},\"priority\":{}}}",
+                i + 1, json_string(&first), json_string(&second), json_items(&shared), shared.priority_sum()
+            );
+        } else {
+            println!("{:>6}  {:<w$}  {:<w$}  {}", i + 1, first, second, describe_items(&shared), w = width);
+        }
+
+        let group_len = (i % group_size) + 1;
+        if group_len == group_size || i + 1 == lines.len() {
+            let group = &lines[i + 1 - group_len..=i];
+            let (from, to, number) = (i + 2 - group_len, i + 1, i / group_size + 1);
+            if group_len < group_size {
+                if json {
+                    println!(
+                        "{{\"type\":\"group\",\"group\":{},\"from_line\":{},\"to_line\":{},\"complete\":false}}",
+                        number, from, to
+                    );
+                } else {
+                    println!("group {} (lines {}-{}): incomplete, not counted", number, from, to);
+                }
+                continue;
+            }
+
+            let badge = group.iter()
+                .map(|r| ItemSet::from_items(r.as_bytes()))
+                .reduce(|a, b| a.intersection(&b))
+                .unwrap_or_default();
+            if json {
+                println!(
+                    "{{\"type\":\"group\",\"group\":{},\"from_line\":{},\"to_line\":{},\"complete\":true,\"badge\":{},\"priority\":{}}}",
+                    number, from, to, json_items(&badge), badge.priority_sum()
+                );
+            } else {
+                println!("group {} (lines {}-{}): badge {}", number, from, to, describe_items(&badge));
+            }
+        }
+    }
+}
+
+fn describe_items(set: &ItemSet) -> String {
+    if set.is_empty() {
+        return "-".to_string();
+    }
+    set.items().zip(set.priorities())
+        .map(|(i, p)| format!("{} ({})", i as char, p))
+        .collect::<Vec<String>>().join(", ")
+}
+
+fn json_items(set: &ItemSet) -> String {
+    let items: Vec<String> = set.items().zip(set.priorities())
+        .map(|(i, p)| format!("{{\"item\":\"{}\",\"priority\":{}}}", i as char, p))
+        .collect();
+    format!("[{}]", items.join(","))
 }
 
-fn group_prio(group: &Vec<String>) -> i32 {
-    assert!(group.len() == 3);
-    let a: HashSet<&u8> = HashSet::from_iter(group[0].as_bytes());
-    let b: HashSet<&u8> = HashSet::from_iter(group[1].as_bytes());
-    let c: HashSet<&u8> = HashSet::from_iter(group[2].as_bytes());
-    let common = a.intersection(&b);
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A set of items, stored as one bit per priority (1..=52).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ItemSet(u64);
+
+// The puzzle does not need the methods marked dead_code; they round off
+// the set API.
+impl ItemSet {
+    fn from_items(items: &[u8]) -> ItemSet {
+        let mut set = ItemSet::default();
+        for &item in items {
+            set.insert(item);
+        }
+        set
+    }
 
-    let mut res = 0;
-    for x in c.iter() {
-        match common.clone().into_iter().find(|&q| q == x) {
-            Some(_) => res += item_priority(**x),
-            None => ()
+    /// Items without a priority are ignored; `validate` rejects them first.
+    fn insert(&mut self, item: u8) {
+        if let Some(prio) = item_priority(item) {
+            self.0 |= 1 << prio;
         }
     }
-    res
+
+    #[allow(dead_code)]
+    fn contains(&self, item: u8) -> bool {
+        item_priority(item).is_some_and(|prio| self.0 & (1 << prio) != 0)
+    }
+
+    #[allow(dead_code)]
+    fn union(&self, other: &ItemSet) -> ItemSet {
+        ItemSet(self.0 | other.0)
+    }
+
+    fn intersection(&self, other: &ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    #[allow(dead_code)]
+    fn difference(&self, other: &ItemSet) -> ItemSet {
+        ItemSet(self.0 & !other.0)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    #[allow(dead_code)]
+    fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Priorities of the items in the set, in ascending order.
+    fn priorities(&self) -> impl Iterator<Item = i32> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let prio = bits.trailing_zeros() as i32;
+            bits &= bits - 1;
+            Some(prio)
+        })
+    }
+
+    fn priority_sum(&self) -> i32 {
+        self.priorities().sum()
+    }
+
+    /// The items in the set, ordered by priority.
+    fn items(&self) -> impl Iterator<Item = u8> {
+        self.priorities().map(|p| match p {
+            1..=26 => b'a' + p as u8 - 1,
+            _ => b'A' + p as u8 - 27,
+        })
+    }
+}
+
+fn line_prio(line: &[u8]) -> i32 {
+    assert!(line.len().is_multiple_of(2));
+    let a = ItemSet::from_items(&line[..line.len()/2]);
+    let b = ItemSet::from_items(&line[line.len()/2..]);
+    a.intersection(&b).priority_sum()
 }
 
-fn item_priority(item: u8) -> i32 {
+/// Priority of the items every rucksack in the group has in common.
+fn group_prio(group: &[String]) -> i32 {
+    group.iter()
+        .map(|r| ItemSet::from_items(r.as_bytes()))
+        .reduce(|a, b| a.intersection(&b))
+        .map(|common| common.priority_sum())
+        .unwrap_or(0)
+}
+
+fn item_priority(item: u8) -> Option<i32> {
     match item {
-        b'a' ..= b'z' => (item - b'a' + 1).into(),
-        b'A' ..= b'Z' => (item - b'A' + 27).into(),
-        _ => 0
+        b'a' ..= b'z' => Some((item - b'a' + 1).into()),
+        b'A' ..= b'Z' => Some((item - b'A' + 27).into()),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<&'static str> {
+        vec![
+            "vJrwpWtwJgWrhcsFMMfFFhFp",
+            "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
+            "PmmdzqPrVvPwwTWBwg",
+            "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
+            "ttgJtRGJQctTZtZT",
+            "CrZsJsPPZsGzwwsLwLmpwMDw",
+        ]
+    }
+
+    #[test]
+    fn test_item_set() {
+        let a = ItemSet::from_items(b"abcA");
+        let b = ItemSet::from_items(b"cAZz");
+        assert!(a.contains(b'a') && a.contains(b'A') && !a.contains(b'Z'));
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.intersection(&b).priorities().collect::<Vec<i32>>(), vec![3, 27]);
+        assert_eq!(a.union(&b).len(), 6);
+        assert_eq!(a.difference(&b).priorities().collect::<Vec<i32>>(), vec![1, 2]);
+        assert!(ItemSet::from_items(b"-1 ").is_empty());
+    }
+
+    #[test]
+    fn test_item_set_items() {
+        assert_eq!(ItemSet::from_items(b"zAaZ").items().collect::<Vec<u8>>(), b"azAZ".to_vec());
+        assert_eq!(describe_items(&ItemSet::from_items(b"pL")), "p (16), L (38)");
+        assert_eq!(describe_items(&ItemSet::default()), "-");
+        assert_eq!(json_items(&ItemSet::from_items(b"r")), r#"[{"item":"r","priority":18}]"#);
+        assert_eq!(json_string("a\"b\\"), r#""a\"b\\""#);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate(&sample().join("\n")), vec![]);
+        assert_eq!(validate("abAB\n\nabc\nab1B\naéBb"), vec![
+            RucksackError::Empty { line: 2 },
+            RucksackError::OddLength { line: 3, length: 3 },
+            RucksackError::InvalidItem { line: 4, column: 3, item: '1' },
+            RucksackError::InvalidItem { line: 5, column: 2, item: 'é' },
+        ]);
+        assert_eq!(RucksackError::InvalidItem { line: 4, column: 3, item: '1' }.to_string(), "line 4, column 3: '1' is not an item");
+        assert_eq!(item_priority(b'-'), None);
+    }
+
+    #[test]
+    fn test_priorities() {
+        let lines = sample();
+        assert_eq!(lines.iter().map(|l| line_prio(l.as_bytes())).sum::<i32>(), 157);
+
+        let groups: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(group_prio(&groups[..3]) + group_prio(&groups[3..]), 70);
+    }
+
+    #[test]
+    fn test_group_sizes() {
+        let groups: Vec<String> = sample().iter().map(|l| l.to_string()).collect();
+        assert_eq!(group_prio(&[]), 0);
+        assert_eq!(group_prio(&groups[..1]), ItemSet::from_items(groups[0].as_bytes()).priority_sum());
+        // f, r, s, F and M
+        assert_eq!(group_prio(&groups[..2]), 6 + 18 + 19 + 32 + 39);
+        assert_eq!(group_prio(&groups[..4]), 0);
     }
 }
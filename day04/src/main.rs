@@ -1,18 +1,43 @@
-use std::{env, fs};
+use std::{env, fmt, fs, process};
 
+/// A closed interval of section IDs, `from..=to`.
+#[derive(Clone, Copy, Debug, PartialEq)]
 struct Range {
     from: i32,
     to: i32,
 }
 
+#[derive(Debug, PartialEq)]
+enum RangeError {
+    Format(String),
+    Number(String),
+    Reversed { from: i32, to: i32 },
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangeError::Format(s) => write!(f, "expected a range like 2-4, got {:?}", s),
+            RangeError::Number(s) => write!(f, "invalid section number {:?}", s),
+            RangeError::Reversed { from, to } => write!(f, "range {}-{} ends before it starts", from, to),
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
+
 impl Range {
-    fn from_def(s: &str) -> Range {
-        let parts: Vec<&str> = s.split("-").collect();
-        assert!(parts.len() == 2);
-        Range {
-            from: parts[0].parse::<i32>().unwrap(),
-            to: parts[1].parse::<i32>().unwrap(),
+    fn new(from: i32, to: i32) -> Result<Range, RangeError> {
+        if from > to {
+            return Err(RangeError::Reversed { from, to });
         }
+        Ok(Range { from, to })
+    }
+
+    fn from_def(s: &str) -> Result<Range, RangeError> {
+        let (from, to) = s.split_once('-').ok_or_else(|| RangeError::Format(s.to_string()))?;
+        let number = |n: &str| n.parse::<i32>().map_err(|_| RangeError::Number(n.to_string()));
+        Range::new(number(from)?, number(to)?)
     }
 
     fn contains(&self, other: &Range) -> bool {
@@ -22,41 +47,327 @@ impl Range {
     fn overlaps(&self, other: &Range) -> bool {
         self.to >= other.from && self.from <= other.to
     }
+
+    fn len(&self) -> usize {
+        (self.to as i64 - self.from as i64 + 1) as usize
+    }
+
+    fn intersection(&self, other: &Range) -> Option<Range> {
+        if self.overlaps(other) {
+            Some(Range { from: self.from.max(other.from), to: self.to.min(other.to) })
+        } else {
+            None
+        }
+    }
+
+    /// The range covering both, if they overlap or touch.
+    fn union(&self, other: &Range) -> Option<Range> {
+        let touches = self.to as i64 + 1 == other.from as i64 || other.to as i64 + 1 == self.from as i64;
+        if self.overlaps(other) || touches {
+            Some(Range { from: self.from.min(other.from), to: self.to.max(other.to) })
+        } else {
+            None
+        }
+    }
+
+    /// The sections of `self` not in `other`, as up to two ranges.
+    #[allow(dead_code)]
+    fn difference(&self, other: &Range) -> Vec<Range> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let mut parts = Vec::new();
+        if self.from < other.from {
+            parts.push(Range { from: self.from, to: other.from - 1 });
+        }
+        if self.to > other.to {
+            parts.push(Range { from: other.to + 1, to: self.to });
+        }
+        parts
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.from, self.to)
+    }
+}
+
+/// One elf's assignment: `elf` is 1 or 2 for the first or second range on
+/// the line.
+#[derive(Debug, PartialEq)]
+struct Assignment {
+    line: usize,
+    elf: usize,
+    range: Range,
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{} ({})", self.line, self.elf, self.range)
+    }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let show = take_switch(&mut args, "--show");
+    let list_pairs = take_switch(&mut args, "--pairs");
+    if args[1] == "global" {
+        let contents = fs::read_to_string(&args[2]).expect("Error reading file");
+        global_analysis(&contents, list_pairs);
+        return;
+    }
+
     let input_path = &args[1];
     let contents = fs::read_to_string(input_path).expect("Error reading file");
 
     let mut total_overlap_count = 0;
     let mut partial_overlap_count = 0;
-    for line in contents.lines() {
-        let ranges: Vec<&str> = line.split(",").collect();
-        assert!(ranges.len() == 2);
-        let range_one = Range::from_def(ranges[0]);
-        let range_two = Range::from_def(ranges[1]);
+    let mut overlapping_sections = 0;
+    for (i, line) in contents.lines().enumerate() {
+        let (range_one, range_two) = parse_pair(line).unwrap_or_else(|e| {
+            eprintln!("line {}: {}", i + 1, e);
+            process::exit(1);
+        });
 
-        total_overlap_count += count_total_overlaps(&range_one, &range_two);
-        partial_overlap_count += count_partial_overlaps(&range_one, &range_two);
+        let total = total_overlap(&range_one, &range_two);
+        let partial = partial_overlap(&range_one, &range_two);
+        if show {
+            let describe = |r: Option<Range>| r.map(|r| r.to_string()).unwrap_or("-".to_string());
+            println!("{}: {} contained, {} overlapping", line, describe(total), describe(partial));
+        }
+
+        total_overlap_count += total.is_some() as i32;
+        partial_overlap_count += partial.is_some() as i32;
+        overlapping_sections += partial.map(|r| r.len()).unwrap_or(0);
     }
 
     println!("total_overlap_count = {}", total_overlap_count);
     println!("partial_overlap_count = {}", partial_overlap_count);
+    println!("overlapping_sections = {}", overlapping_sections);
 }
 
-fn count_total_overlaps(a: &Range, b: &Range) -> i32 {
-    if a.contains(b) || b.contains(a) {
-        1
-    } else {
-        0
+/// Removes the switch `name` from `args` and tells whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+fn global_analysis(contents: &str, list_pairs: bool) {
+    let assignments = parse_assignments(contents).unwrap_or_else(|(line, e)| {
+        eprintln!("line {}: {}", line, e);
+        process::exit(1);
+    });
+
+    let pairs = overlapping_pairs(&assignments);
+    let containing = pairs.iter().filter(|p| p.2).count();
+    if list_pairs {
+        for &(a, b, contains) in &pairs {
+            let (mut a, mut b) = (&assignments[a], &assignments[b]);
+            let section = a.range.intersection(&b.range).expect("pairs overlap");
+            if contains {
+                if !a.range.contains(&b.range) {
+                    (a, b) = (b, a);
+                }
+                println!("{} contains {}", a, b);
+            } else {
+                println!("{} overlaps {} on {}", a, b, section);
+            }
+        }
     }
+    println!("overlapping pairs = {}", pairs.len());
+    println!("containing pairs = {}", containing);
+
+    let (count, sections) = most_covered(&assignments);
+    let sections: Vec<String> = sections.iter().map(|r| r.to_string()).collect();
+    println!("most elves on one section = {} on {}", count, sections.join(", "));
+
+    let gaps: Vec<String> = uncovered(&assignments).iter().map(|r| r.to_string()).collect();
+    println!("uncovered sections = {}", if gaps.is_empty() { "-".to_string() } else { gaps.join(", ") });
 }
 
-fn count_partial_overlaps(a: &Range, b: &Range) -> i32 {
-    if a.overlaps(b) {
-        1
+/// Every range in the file, in order, or the first error with its line.
+fn parse_assignments(contents: &str) -> Result<Vec<Assignment>, (usize, RangeError)> {
+    let mut assignments = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let (one, two) = parse_pair(line).map_err(|e| (i + 1, e))?;
+        assignments.push(Assignment { line: i + 1, elf: 1, range: one });
+        assignments.push(Assignment { line: i + 1, elf: 2, range: two });
+    }
+    Ok(assignments)
+}
+
+/// All pairs of assignments whose ranges share a section, as indices with
+/// the earlier assignment first and a flag telling whether one range
+/// contains the other. Sorting by start and sweeping keeps this at
+/// O(n log n + pairs).
+fn overlapping_pairs(assignments: &[Assignment]) -> Vec<(usize, usize, bool)> {
+    let mut order: Vec<usize> = (0..assignments.len()).collect();
+    order.sort_by_key(|&i| (assignments[i].range.from, assignments[i].range.to));
+
+    let mut pairs = Vec::new();
+    for (k, &i) in order.iter().enumerate() {
+        let a = &assignments[i].range;
+        for &j in order[k + 1..].iter().take_while(|&&j| assignments[j].range.from <= a.to) {
+            let b = &assignments[j].range;
+            pairs.push((i.min(j), i.max(j), a.contains(b) || b.contains(a)));
+        }
+    }
+    pairs.sort();
+    pairs
+}
+
+/// The largest number of elves assigned to any one section, and the
+/// sections where that many elves meet.
+fn most_covered(assignments: &[Assignment]) -> (usize, Vec<Range>) {
+    // +1 where a range starts, -1 just after it ends
+    let mut events: Vec<(i64, i32)> = assignments.iter()
+        .flat_map(|a| [(a.range.from as i64, 1), (a.range.to as i64 + 1, -1)])
+        .collect();
+    events.sort();
+
+    let mut best = 0;
+    let mut sections: Vec<Range> = Vec::new();
+    let mut count: i32 = 0;
+    let mut i = 0;
+    while i < events.len() {
+        let position = events[i].0;
+        while i < events.len() && events[i].0 == position {
+            count += events[i].1;
+            i += 1;
+        }
+        if count <= 0 || i == events.len() {
+            continue;
+        }
+        let section = Range { from: position as i32, to: (events[i].0 - 1) as i32 };
+        if count as usize > best {
+            best = count as usize;
+            sections.clear();
+        }
+        if count as usize == best {
+            match sections.last().and_then(|last| last.union(&section)) {
+                Some(merged) => *sections.last_mut().unwrap() = merged,
+                None => sections.push(section),
+            }
+        }
+    }
+    (best, sections)
+}
+
+/// Sections between the lowest and highest assigned section that no elf
+/// is assigned to.
+fn uncovered(assignments: &[Assignment]) -> Vec<Range> {
+    let mut ranges: Vec<Range> = assignments.iter().map(|a| a.range).collect();
+    ranges.sort_by_key(|r| r.from);
+
+    let mut covered: Vec<Range> = Vec::new();
+    for r in ranges {
+        match covered.last().and_then(|last| last.union(&r)) {
+            Some(merged) => *covered.last_mut().unwrap() = merged,
+            None => covered.push(r),
+        }
+    }
+    covered.windows(2).map(|w| Range { from: w[0].to + 1, to: w[1].from - 1 }).collect()
+}
+
+fn parse_pair(line: &str) -> Result<(Range, Range), RangeError> {
+    let (one, two) = line.split_once(',').ok_or_else(|| RangeError::Format(line.to_string()))?;
+    Ok((Range::from_def(one)?, Range::from_def(two)?))
+}
+
+/// The smaller range, if one of them contains the other.
+fn total_overlap(a: &Range, b: &Range) -> Option<Range> {
+    if a.contains(b) {
+        Some(*b)
+    } else if b.contains(a) {
+        Some(*a)
     } else {
-        0
+        None
+    }
+}
+
+/// The sections both ranges cover.
+fn partial_overlap(a: &Range, b: &Range) -> Option<Range> {
+    a.intersection(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> &'static str {
+        "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8"
+    }
+
+    fn r(from: i32, to: i32) -> Range {
+        Range::new(from, to).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Range::from_def("2-4"), Ok(r(2, 4)));
+        assert_eq!(Range::from_def("4-2"), Err(RangeError::Reversed { from: 4, to: 2 }));
+        assert_eq!(Range::from_def("4"), Err(RangeError::Format("4".to_string())));
+        assert_eq!(Range::from_def("a-2"), Err(RangeError::Number("a".to_string())));
+        assert_eq!(parse_pair("2-4,6-8"), Ok((r(2, 4), r(6, 8))));
+        assert!(parse_pair("2-4 6-8").is_err());
+    }
+
+    #[test]
+    fn test_interval_algebra() {
+        assert_eq!(r(2, 6).len(), 5);
+        assert_eq!(r(2, 6).intersection(&r(4, 8)), Some(r(4, 6)));
+        assert_eq!(r(2, 3).intersection(&r(4, 5)), None);
+        assert_eq!(r(2, 3).union(&r(4, 5)), Some(r(2, 5)));
+        assert_eq!(r(2, 3).union(&r(5, 6)), None);
+        assert_eq!(r(2, 8).difference(&r(3, 7)), vec![r(2, 2), r(8, 8)]);
+        assert_eq!(r(2, 6).difference(&r(4, 8)), vec![r(2, 3)]);
+        assert_eq!(r(3, 7).difference(&r(2, 8)), vec![]);
+        assert_eq!(r(2, 3).difference(&r(5, 6)), vec![r(2, 3)]);
+        assert_eq!(r(6, 8).to_string(), "6-8");
+    }
+
+    #[test]
+    fn test_global_analysis() {
+        let assignments = parse_assignments(sample()).unwrap();
+        assert_eq!(assignments[5], Assignment { line: 3, elf: 2, range: r(7, 9) });
+
+        let pairs = overlapping_pairs(&assignments);
+        // 2-4 meets 2-3, 2-8, 2-6 and 4-5, 4-6, 4-8
+        assert!(pairs.contains(&(0, 2, true)));
+        assert!(pairs.contains(&(0, 3, false)));
+        assert!(pairs.contains(&(6, 7, true)));
+        assert!(!pairs.contains(&(0, 1, false)));
+        let brute_force = (0..assignments.len())
+            .flat_map(|i| (i + 1..assignments.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| assignments[i].range.overlaps(&assignments[j].range))
+            .count();
+        assert_eq!(pairs.len(), brute_force);
+
+        assert_eq!(most_covered(&assignments), (8, vec![r(6, 6)]));
+        assert_eq!(uncovered(&assignments), vec![]);
+
+        let sparse = parse_assignments("1-2,5-6\n9-9,4-5").unwrap();
+        assert_eq!(uncovered(&sparse), vec![r(3, 3), r(7, 8)]);
+        assert_eq!(most_covered(&sparse), (2, vec![r(5, 5)]));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let pairs: Vec<(Range, Range)> = sample().lines().map(|l| parse_pair(l).unwrap()).collect();
+        let total: Vec<Option<Range>> = pairs.iter().map(|(a, b)| total_overlap(a, b)).collect();
+        let partial: Vec<Option<Range>> = pairs.iter().map(|(a, b)| partial_overlap(a, b)).collect();
+        assert_eq!(total, vec![None, None, None, Some(r(3, 7)), Some(r(6, 6)), None]);
+        assert_eq!(partial, vec![None, None, Some(r(7, 7)), Some(r(3, 7)), Some(r(6, 6)), Some(r(4, 6))]);
     }
 }
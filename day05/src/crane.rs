@@ -0,0 +1,145 @@
+use crate::{Board, MoveErrorKind};
+
+/// A crane model: how a `move N from A to B` step shifts the crates.
+pub trait Crane {
+    fn name(&self) -> String;
+    fn move_items(&mut self, board: &mut Board, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind>;
+
+    /// Whether a move does the same thing no matter which moves came before.
+    fn stateless(&self) -> bool {
+        true
+    }
+}
+
+/// Moves one crate at a time, so the moved crates end up reversed.
+pub struct CraneMover9000;
+
+impl Crane for CraneMover9000 {
+    fn name(&self) -> String {
+        "CraneMover 9000".to_string()
+    }
+
+    fn move_items(&mut self, board: &mut Board, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        board.move_items(count, from, to)
+    }
+}
+
+/// Moves all crates at once, keeping their order.
+pub struct CraneMover9001;
+
+impl Crane for CraneMover9001 {
+    fn name(&self) -> String {
+        "CraneMover 9001".to_string()
+    }
+
+    fn move_items(&mut self, board: &mut Board, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        board.move_items_in_order(count, from, to)
+    }
+}
+
+/// Lifts at most `capacity` crates per grab, keeping their order within a
+/// grab.
+pub struct CapacityLimited {
+    pub capacity: usize,
+}
+
+impl Crane for CapacityLimited {
+    fn name(&self) -> String {
+        format!("capacity-limited crane ({} per grab)", self.capacity)
+    }
+
+    fn move_items(&mut self, board: &mut Board, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        board.check(count, from, to)?;
+        let mut left = count;
+        while left > 0 {
+            let grab = left.min(self.capacity);
+            board.move_items_in_order(grab, from, to)?;
+            left -= grab;
+        }
+        Ok(())
+    }
+}
+
+/// Moves all crates at once, but every other grab comes out reversed.
+pub struct Alternating {
+    pub grabs: usize,
+}
+
+impl Crane for Alternating {
+    fn name(&self) -> String {
+        "alternating crane".to_string()
+    }
+
+    fn move_items(&mut self, board: &mut Board, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        if self.grabs.is_multiple_of(2) {
+            board.move_items_in_order(count, from, to)?;
+        } else {
+            board.move_items(count, from, to)?;
+        }
+        self.grabs += 1;
+        Ok(())
+    }
+
+    fn stateless(&self) -> bool {
+        false
+    }
+}
+
+pub const DEFAULT_CRANES: [&str; 2] = ["9000", "9001"];
+
+/// Builds a crane from a spec: `9000`, `9001`, `capacity:K` or `alternating`.
+pub fn new_crane(spec: &str) -> Result<Box<dyn Crane>, String> {
+    match spec.split_once(':') {
+        None if spec == "9000" => Ok(Box::new(CraneMover9000)),
+        None if spec == "9001" => Ok(Box::new(CraneMover9001)),
+        None if spec == "alternating" => Ok(Box::new(Alternating { grabs: 0 })),
+        Some(("capacity", k)) => match k.parse() {
+            Ok(capacity) if capacity > 0 => Ok(Box::new(CapacityLimited { capacity })),
+            _ => Err(format!("capacity must be a positive number, got {:?}", k)),
+        },
+        _ => Err(format!("unknown crane {:?}, use 9000, 9001, capacity:K or alternating", spec)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> Board {
+        Board::new(vec![vec!['A', 'B', 'C', 'D', 'E'], vec![]])
+    }
+
+    fn moved(spec: &str, moves: &[usize]) -> Vec<char> {
+        let mut crane = new_crane(spec).unwrap();
+        let mut b = board();
+        for &count in moves {
+            crane.move_items(&mut b, count, 1, 2).unwrap();
+        }
+        b.labels()[1].clone()
+    }
+
+    #[test]
+    fn test_cranes() {
+        assert_eq!(moved("9000", &[5]), vec!['E', 'D', 'C', 'B', 'A']);
+        assert_eq!(moved("9001", &[5]), vec!['A', 'B', 'C', 'D', 'E']);
+        assert_eq!(moved("capacity:2", &[5]), vec!['D', 'E', 'B', 'C', 'A']);
+        assert_eq!(moved("capacity:5", &[5]), moved("9001", &[5]));
+        assert_eq!(moved("alternating", &[2, 2, 1]), vec!['D', 'E', 'C', 'B', 'A']);
+    }
+
+    #[test]
+    fn test_crane_specs() {
+        assert!(new_crane("capacity:0").is_err());
+        assert!(new_crane("capacity:x").is_err());
+        assert!(new_crane("9002").is_err());
+        assert_eq!(new_crane("capacity:3").unwrap().name(), "capacity-limited crane (3 per grab)");
+    }
+
+    #[test]
+    fn test_invalid_move_leaves_board() {
+        let mut b = board();
+        let error = new_crane("capacity:2").unwrap().move_items(&mut b, 6, 1, 2);
+        assert_eq!(error, Err(MoveErrorKind::TooFewCrates { stack: 1, height: 5, count: 6 }));
+        assert_eq!(b.labels(), board().labels());
+    }
+}
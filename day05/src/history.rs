@@ -0,0 +1,132 @@
+use crate::{Board, Crane, Crate, MoveErrorKind};
+
+/// One applied move, with the stacks it touched before and after so it can
+/// be undone and redone without asking the crane again.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+    before: [Vec<Crate>; 2],
+    after: [Vec<Crate>; 2],
+}
+
+impl Board {
+    /// Moves crates with `crane` and records the move. Anything undone
+    /// before is forgotten. An invalid move leaves the board as it was.
+    pub fn apply(&mut self, crane: &mut dyn Crane, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        self.check(count, from, to)?;
+        let number = self.history.len() + 1;
+        let before = [self.stacks[from-1].clone(), self.stacks[to-1].clone()];
+        crane.move_items(self, count, from, to)?;
+
+        let height = self.stacks[to-1].len();
+        for c in &mut self.stacks[to-1][height - count..] {
+            c.trail.push((number, from, to));
+        }
+
+        let after = [self.stacks[from-1].clone(), self.stacks[to-1].clone()];
+        self.history.push(Step { count, from, to, before, after });
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Reverts the last move, if there is one.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(step) => {
+                self.stacks[step.from-1] = step.before[0].clone();
+                self.stacks[step.to-1] = step.before[1].clone();
+                self.undone.push(step);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Applies the last undone move again, if there is one.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(step) => {
+                self.stacks[step.from-1] = step.after[0].clone();
+                self.stacks[step.to-1] = step.after[1].clone();
+                self.history.push(step);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of moves currently applied.
+    pub fn step(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Number of moves known, applied or undone.
+    pub fn step_count(&self) -> usize {
+        self.history.len() + self.undone.len()
+    }
+
+    /// Undoes or redoes moves until `n` are applied, or as close as the
+    /// known moves allow.
+    pub fn goto(&mut self, n: usize) {
+        while self.step() > n && self.undo() {}
+        while self.step() < n && self.redo() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crane::{CraneMover9000, CraneMover9001};
+    use crate::Board;
+
+    fn board() -> Board {
+        Board::new(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']])
+    }
+
+    const MOVES: [(usize, usize, usize); 4] = [(1, 2, 1), (3, 1, 3), (2, 2, 1), (1, 1, 2)];
+
+    #[test]
+    fn test_undo_redo() {
+        let mut b = board();
+        for (count, from, to) in MOVES {
+            b.apply(&mut CraneMover9000, count, from, to).unwrap();
+        }
+        let end = b.labels();
+        assert_eq!(end, vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']]);
+
+        b.goto(0);
+        assert_eq!(b.labels(), board().labels());
+        assert!(!b.undo());
+
+        b.goto(2);
+        assert_eq!(b.labels(), vec![vec![], vec!['M', 'C'], vec!['P', 'D', 'N', 'Z']]);
+        assert!(b.redo() && b.redo() && !b.redo());
+        assert_eq!(b.labels(), end);
+        assert_eq!((b.step(), b.step_count()), (4, 4));
+
+        b.goto(3);
+        b.apply(&mut CraneMover9001, 1, 1, 3).unwrap();
+        assert_eq!(b.step_count(), 4);
+        assert_eq!(b.labels(), vec![vec!['C'], vec![], vec!['P', 'D', 'N', 'Z', 'M']]);
+    }
+
+    #[test]
+    fn test_trail() {
+        let mut b = board();
+        for (count, from, to) in MOVES {
+            b.apply(&mut CraneMover9000, count, from, to).unwrap();
+        }
+        let z = &b.stacks[2][3];
+        assert_eq!((z.label, z.origin), ('Z', 1));
+        assert_eq!(z.trail, vec![(2, 1, 3)]);
+        let c = &b.stacks[0][0];
+        assert_eq!((c.label, c.origin), ('C', 2));
+        assert_eq!(c.trail, vec![(3, 2, 1)]);
+        let m = &b.stacks[1][0];
+        assert_eq!(m.trail, vec![(3, 2, 1), (4, 1, 2)]);
+
+        b.goto(2);
+        assert_eq!(b.stacks[1][0].trail, vec![]);
+    }
+}
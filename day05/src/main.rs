@@ -1,115 +1,647 @@
-use std::{env, fs};
+use std::{env, fmt, fs, process};
 
+mod crane;
+mod history;
+mod solver;
+
+use crane::Crane;
+use history::Step;
+
+/// A crate, the stack it started on and the moves that carried it, as
+/// `(move number, from, to)`.
+#[derive(Clone, Debug, PartialEq)]
+struct Crate {
+    label: char,
+    origin: usize,
+    trail: Vec<(usize, usize, usize)>,
+}
+
+/// `label_row`, the character column of each stack's crates, the width of
+/// each crate row (bottom first) and the line ending are kept as drawn so
+/// the board can be written back in the same layout.
 #[derive(Debug)]
 struct Board {
-    stacks: Vec<Vec<char>>,
+    stacks: Vec<Vec<Crate>>,
+    label_row: String,
+    columns: Vec<usize>,
+    row_widths: Vec<usize>,
+    line_ending: &'static str,
+    history: Vec<Step>,
+    undone: Vec<Step>,
+}
+
+#[derive(Debug, PartialEq)]
+enum MoveErrorKind {
+    Malformed { text: String },
+    NoSuchStack { stack: usize, stacks: usize },
+    TooFewCrates { stack: usize, height: usize, count: usize },
+}
+
+/// A move that cannot be made, with its 1-based line in the input.
+#[derive(Debug, PartialEq)]
+struct MoveError {
+    line: usize,
+    kind: MoveErrorKind,
 }
 
 fn main() {
-    let input_path: String = env::args().skip(1).next().expect("No input file gvien");
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("solve") {
+        solve(&args[1..]);
+        return;
+    }
+
+    let at: Option<usize> = take_flag(&mut args, "--at").map(|n| n.parse().expect("--at needs a move number"));
+    let replay = take_switch(&mut args, "--replay");
+    let trail = take_switch(&mut args, "--trail");
+    let save = take_switch(&mut args, "--save");
+    let check = take_switch(&mut args, "--check");
+    let input_path: String = args.first().expect("No input file gvien").clone();
+    let mut crane_specs: Vec<String> = args[1..].to_vec();
+    if crane_specs.is_empty() {
+        crane_specs = crane::DEFAULT_CRANES.iter().map(|c| c.to_string()).collect();
+    }
+    let cranes: Vec<Box<dyn Crane>> = crane_specs.iter()
+        .map(|spec| crane::new_crane(spec).unwrap_or_else(fail))
+        .collect();
+    assert!(!save || cranes.len() == 1, "--save needs exactly one crane");
+
     let contents = fs::read_to_string(input_path).expect("Error reading file");
+    let (stack_description, moves) = parse_input(&contents);
 
-    let mut line_iter = contents.lines().into_iter();
+    if check {
+        let mut invalid = false;
+        for mut crane in cranes {
+            let errors = Board::parse(&stack_description).run(crane.as_mut(), &moves, true);
+            for e in &errors {
+                eprintln!("{}: {}", crane.name(), e);
+            }
+            println!("{}: {} of {} moves invalid", crane.name(), errors.len(), moves.len());
+            invalid |= !errors.is_empty();
+        }
+        if invalid {
+            process::exit(1);
+        }
+        return;
+    }
+
+    for (i, mut crane) in cranes.into_iter().enumerate() {
+        let mut board = Board::parse(&stack_description);
+        if let Some(e) = board.run(crane.as_mut(), &moves, false).first() {
+            eprintln!("{}: {}", crane.name(), e);
+            process::exit(1);
+        }
+
+        if save {
+            if let Some(n) = at {
+                board.goto(n);
+            }
+            print!("{}", board.serialize());
+            return;
+        }
+
+        if i > 0 {
+            println!();
+        }
+        println!("{}", crane.name());
+        if replay {
+            board.goto(0);
+            println!("start");
+            board.show();
+            while board.redo() {
+                let step = board.history.last().expect("just redone");
+                println!("move {}: {} from {} to {}", board.step(), step.count, step.from, step.to);
+                board.show();
+            }
+        }
+        if let Some(n) = at {
+            board.goto(n);
+            println!("after move {} of {}", board.step(), board.step_count());
+        }
+        if !replay || at.is_some() {
+            board.show();
+        }
+        board.show_message();
+        if trail {
+            board.show_trails();
+        }
+    }
+}
+
+/// `solve <start> <target> [crane]`: prints a shortest move list from one
+/// drawing to the other. Moves in either file are ignored.
+fn solve(args: &[String]) {
+    assert!(args.len() >= 2, "solve needs a start and a target file");
+    let read = |path: &String| fs::read_to_string(path).expect("Error reading file");
+    let (start, target) = (read(&args[0]), read(&args[1]));
+    let spec = args.get(2).map(String::as_str).unwrap_or("9000");
+    let mut crane = crane::new_crane(spec).unwrap_or_else(fail);
+    if !crane.stateless() {
+        fail::<()>(format!("the solver needs a crane that keeps no state between moves, not the {}", crane.name()));
+    }
+
+    let start = Board::parse(&parse_input(&start).0);
+    let target = Board::parse(&parse_input(&target).0);
+    match solver::solve(&start, &target, crane.as_mut()) {
+        Ok(moves) => {
+            for (count, from, to) in &moves {
+                println!("move {} from {} to {}", count, from, to);
+            }
+            eprintln!("{} moves with {}", moves.len(), crane.name());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Splits the input into the crate drawing, bottom-up with the label row
+/// first, and the non-blank move lines with their 1-based line numbers.
+/// Drawing lines keep the `\r` of CRLF input so the board can write it back.
+fn parse_input(contents: &str) -> (Vec<&str>, Vec<(usize, &str)>) {
+    let mut line_iter = contents.split('\n').enumerate();
 
     // parse stack
     let mut stack_description: Vec<&str> = Vec::new();
-    loop {
-        let line = line_iter.next().expect("input ended unexpectedly");
-        if line == "" {
+    for (_, line) in line_iter.by_ref() {
+        if line.trim_end_matches('\r').is_empty() {
             break;
         }
         stack_description.push(line);
     }
+    assert!(!stack_description.is_empty(), "input has no crate drawing");
 
     stack_description.reverse();
-    let mut board_one = Board::parse(&stack_description);
-    let mut board_two = Board::parse(&stack_description);
+    let moves = line_iter
+        .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+    (stack_description, moves)
+}
+
+fn fail<T>(message: String) -> T {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+/// Removes the switch `name` from `args` and tells whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Removes `name` and the value following it from `args`.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    args.remove(i);
+    if i < args.len() {
+        Some(args.remove(i))
+    } else {
+        panic!("{} needs a value", name)
+    }
+}
 
-    for line in line_iter {
-        let (count, from, to) = parse_line(line);
-        board_one.move_items(count, from, to);
-        board_two.move_items_in_order(count, from, to);
+impl fmt::Display for MoveErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveErrorKind::Malformed { text } =>
+                write!(f, "expected \"move N from A to B\", got {:?}", text),
+            MoveErrorKind::NoSuchStack { stack, stacks } =>
+                write!(f, "there is no stack {}, only 1 to {}", stack, stacks),
+            MoveErrorKind::TooFewCrates { stack, height, count } =>
+                write!(f, "cannot take {} crates from stack {}, which holds {}", count, stack, height),
+        }
+    }
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
     }
+}
 
-    println!("CraneMover 9000");
-    board_one.show();
-    board_one.show_message();
-    println!();
+impl std::error::Error for MoveError {}
 
-    println!("CraneMover 9001");
-    board_two.show();
-    board_two.show_message();
+impl fmt::Display for Crate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label)
+    }
 }
 
 impl Board {
-    fn parse(lines: &Vec<&str>) -> Board {
-        let stack_count: usize = lines[0].trim().split(" ").last().expect("cannot parse stack count").parse().unwrap();
+    /// A board with the given crates on each stack, bottom first, and
+    /// labels spaced the way the puzzle draws them.
+    fn new(stacks: Vec<Vec<char>>) -> Board {
+        let label_row = (1..=stacks.len()).map(|i| format!("{:>2} ", i)).collect::<Vec<String>>().join(" ");
+        let columns = crate_columns(&label_row);
+        let stacks = stacks.into_iter().enumerate()
+            .map(|(i, s)| s.into_iter().map(|label| Crate { label, origin: i + 1, trail: vec![] }).collect())
+            .collect();
+        Board { stacks, label_row, columns, row_widths: vec![], line_ending: "\n", history: vec![], undone: vec![] }
+    }
+
+    /// The crate labels on each stack, bottom first.
+    fn labels(&self) -> Vec<Vec<char>> {
+        self.stacks.iter().map(|s| s.iter().map(|c| c.label).collect()).collect()
+    }
 
-        let mut board = Board{ stacks: Vec::new() };
-        board.stacks.resize(stack_count, Vec::<char>::new());
+    /// Parses the crate drawing bottom-up, label row first. Crates are
+    /// matched to stacks by column, so labels may have several digits and
+    /// rows may be ragged. Lines may end in `\r`, as in CRLF input.
+    fn parse(lines: &[&str]) -> Board {
+        let line_ending = if lines[0].ends_with('\r') { "\r\n" } else { "\n" };
+        let lines: Vec<&str> = lines.iter().map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+        let labels = label_columns(lines[0]);
+        assert!(!labels.is_empty(), "cannot find stack labels");
 
+        let mut stacks: Vec<Vec<char>> = vec![Vec::new(); labels.len()];
+        let mut columns = crate_columns(lines[0]);
         for line in &lines[1..] {
-            let mut chars = line.chars().skip(1).step_by(4);
-            for i in 0..stack_count {
-                match chars.next() {
-                    Some(' ') => (),
-                    Some(c) => board.stacks[i].push(c),
-                    _ => ()
+            let chars: Vec<char> = line.chars().collect();
+            for (i, w) in chars.windows(3).enumerate() {
+                if w[0] == '[' && w[2] == ']' {
+                    let stack = nearest_label(&labels, i + 1);
+                    if stacks[stack].is_empty() {
+                        columns[stack] = i + 1;
+                    }
+                    stacks[stack].push(w[1]);
                 }
             }
         }
 
+        let mut board = Board::new(stacks);
+        board.label_row = lines[0].to_string();
+        board.columns = columns;
+        board.row_widths = lines[1..].iter().map(|line| line.chars().count()).collect();
+        board.line_ending = line_ending;
         board
     }
 
-    fn move_items(&mut self, count: usize, from: usize, to: usize) {
-        assert!(0 < from && from <= self.stacks.len());
-        assert!(0 < to && to <= self.stacks.len());
+    /// Draws the board the way the puzzle input does: one row of bracketed
+    /// crates per height, top first, with each crate in its stack's column.
+    /// Rows at a drawn height are padded to that row's drawn width; rows
+    /// above them are padded to the label row's width if the top drawn row
+    /// was. So before any move the drawing is written back byte for byte.
+    fn drawing(&self) -> String {
+        let label_width = self.label_row.chars().count();
+        let new_row_width = match self.row_widths.last() {
+            Some(&top) if top < label_width => 0,
+            _ => label_width,
+        };
+        let height = self.stacks.iter().map(|s| s.len()).max().unwrap_or(0);
 
-        for _ in 0..count {
-            if let Some(c) = self.stacks[from-1].pop() {
-                self.stacks[to-1].push(c);
-            } else {
-                panic!("Stack {} is empty", from);
+        let mut out = String::new();
+        for level in (0..height).rev() {
+            let width = self.row_widths.get(level).copied().unwrap_or(new_row_width);
+            let mut row: Vec<char> = vec![' '; width];
+            for (stack, &column) in self.stacks.iter().zip(&self.columns) {
+                if let Some(c) = stack.get(level) {
+                    if row.len() < column + 2 {
+                        row.resize(column + 2, ' ');
+                    }
+                    row[column - 1] = '[';
+                    row[column] = c.label;
+                    row[column + 1] = ']';
+                }
+            }
+            out.extend(row);
+            out.push_str(self.line_ending);
+        }
+        out.push_str(&self.label_row);
+        out.push_str(self.line_ending);
+        out
+    }
+
+    /// The drawing followed by the moves not yet applied, as a valid input.
+    fn serialize(&self) -> String {
+        let mut out = self.drawing();
+        out.push_str(self.line_ending);
+        for step in self.undone.iter().rev() {
+            out.push_str(&format!("move {} from {} to {}{}", step.count, step.from, step.to, self.line_ending));
+        }
+        out
+    }
+
+    /// Applies the move lines with `crane`. An invalid move ends the run,
+    /// or is skipped when `keep_going` is set; either way it is returned.
+    fn run(&mut self, crane: &mut dyn Crane, moves: &[(usize, &str)], keep_going: bool) -> Vec<MoveError> {
+        let mut errors = Vec::new();
+        for &(line, text) in moves {
+            let result = parse_line(text).and_then(|(count, from, to)| self.apply(crane, count, from, to));
+            if let Err(kind) = result {
+                errors.push(MoveError { line, kind });
+                if !keep_going {
+                    break;
+                }
             }
         }
+        errors
     }
 
-    fn move_items_in_order(&mut self, count: usize, from: usize, to: usize) {
-        assert!(0 < from && from <= self.stacks.len());
-        assert!(0 < to && to <= self.stacks.len());
+    /// Whether both stacks exist and `from` holds at least `count` crates.
+    fn check(&self, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        let stacks = self.stacks.len();
+        for stack in [from, to] {
+            if stack == 0 || stack > stacks {
+                return Err(MoveErrorKind::NoSuchStack { stack, stacks });
+            }
+        }
+        let height = self.stacks[from-1].len();
+        if height < count {
+            return Err(MoveErrorKind::TooFewCrates { stack: from, height, count });
+        }
+        Ok(())
+    }
 
+    fn move_items(&mut self, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        self.check(count, from, to)?;
+        for _ in 0..count {
+            let c = self.stacks[from-1].pop().expect("checked height");
+            self.stacks[to-1].push(c);
+        }
+        Ok(())
+    }
+
+    fn move_items_in_order(&mut self, count: usize, from: usize, to: usize) -> Result<(), MoveErrorKind> {
+        self.check(count, from, to)?;
         let from_size = self.stacks[from-1].len();
-        assert!(from_size >= count);
         let mut substack = self.stacks[from-1].split_off(from_size - count);
         self.stacks[to-1].append(&mut substack);
+        Ok(())
     }
 
     fn show(&self) {
-        let mut i = 0;
+        for (i, s) in self.labels().iter().enumerate() {
+            println!("{}: {}", i + 1, s.iter().collect::<String>());
+        }
+    }
+
+    /// The top crate of every stack, with `-` for empty stacks.
+    fn show_message(&self) {
         for s in &self.stacks {
-            i += 1;
-            print!("{}: ", i);
+            match s.last() {
+                Some(c) => print!("{}", c),
+                None => print!("-"),
+            }
+        }
+        println!();
+    }
+
+    fn show_trails(&self) {
+        for (i, s) in self.stacks.iter().enumerate() {
             for c in s {
-                print!("{}", c);
+                let moves: Vec<String> = c.trail.iter()
+                    .map(|(n, from, to)| format!("move {}: {} -> {}", n, from, to))
+                    .collect();
+                let moves = if moves.is_empty() { "never moved".to_string() } else { moves.join(", ") };
+                println!("{} on {} (from {}): {}", c, i + 1, c.origin, moves);
             }
-            println!("")
         }
     }
+}
 
-    fn show_message(&self) {
-        for s in &self.stacks {
-            print!("{}", s.last().unwrap());
+/// Character columns `(first, last)` of every label in the label row.
+fn label_columns(line: &str) -> Vec<(usize, usize)> {
+    let mut labels = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, c) in line.chars().chain([' ']).enumerate() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                labels.push((s, i - 1));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    labels
+}
+
+/// Where crates go when a stack has none drawn: under the last character of
+/// its label, but at least one column in so the opening bracket fits.
+fn crate_columns(label_row: &str) -> Vec<usize> {
+    label_columns(label_row).iter().map(|&(_, last)| last.max(1)).collect()
+}
+
+/// Index of the label closest to `column`.
+fn nearest_label(labels: &[(usize, usize)], column: usize) -> usize {
+    let distance = |&(first, last): &(usize, usize)| {
+        if column < first {
+            first - column
+        } else {
+            column.saturating_sub(last)
         }
-        println!("");
+    };
+    (0..labels.len()).min_by_key(|&i| distance(&labels[i])).expect("no labels")
+}
+
+/// Parses `move N from A to B` into `(N, A, B)`.
+fn parse_line(line: &str) -> Result<(usize, usize, usize), MoveErrorKind> {
+    let malformed = || MoveErrorKind::Malformed { text: line.to_string() };
+    match line.split_whitespace().collect::<Vec<&str>>()[..] {
+        ["move", count, "from", from, "to", to] => {
+            let number = |s: &str| s.parse::<usize>().map_err(|_| malformed());
+            Ok((number(count)?, number(from)?, number(to)?))
+        }
+        _ => Err(malformed()),
     }
 }
 
-fn parse_line(line: &str) -> (usize, usize, usize) {
-    let tokens: Vec<&str> = line.split(" ").skip(1).step_by(2).collect();
-    assert!(tokens.len() == 3);
-    let count: usize = tokens[0].parse().unwrap();
-    let from: usize = tokens[1].parse().unwrap();
-    let to: usize = tokens[2].parse().unwrap();
-    (count, from, to)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawing(s: &str) -> Vec<&str> {
+        let mut lines: Vec<&str> = s.lines().collect();
+        lines.reverse();
+        lines
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let board = Board::parse(&drawing("    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 "));
+        assert_eq!(board.labels(), vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+    }
+
+    #[test]
+    fn test_parse_two_digit_labels_and_ragged_rows() {
+        let board = Board::parse(&drawing("                                    [K]
+[A]                                 [J] [L]
+[B] [C] [D] [E] [F] [G] [H] [I]     [X] [Y]   
+ 1   2   3   4   5   6   7   8   9  10  11"));
+        let stacks = board.labels();
+        assert_eq!(stacks.len(), 11);
+        assert_eq!(stacks[0], vec!['B', 'A']);
+        assert_eq!(stacks[8], Vec::<char>::new());
+        assert_eq!(stacks[9], vec!['X', 'J', 'K']);
+        assert_eq!(stacks[10], vec!['Y', 'L']);
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let input = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+        let (description, moves) = parse_input(input);
+        let mut board = Board::parse(&description);
+        assert_eq!(board.run(&mut crane::CraneMover9000, &moves, false), vec![]);
+
+        board.goto(0);
+        assert_eq!(board.serialize(), input);
+
+        board.goto(2);
+        assert_eq!(board.serialize(), "        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3 
+
+move 2 from 2 to 1
+move 1 from 1 to 2
+");
+    }
+
+    #[test]
+    fn test_serialize_unpadded() {
+        let input = "    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+";
+        let (description, moves) = parse_input(input);
+        let mut board = Board::parse(&description);
+        assert_eq!(board.run(&mut crane::CraneMover9000, &moves, false), vec![]);
+        assert_eq!(board.drawing(), "        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3
+");
+        board.goto(0);
+        assert_eq!(board.serialize(), input);
+    }
+
+    #[test]
+    fn test_serialize_ragged_padding() {
+        let input = "    [D]
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+";
+        let (description, moves) = parse_input(input);
+        let mut board = Board::parse(&description);
+        assert_eq!(board.run(&mut crane::CraneMover9000, &moves, false), vec![]);
+        board.goto(0);
+        assert_eq!(board.serialize(), input);
+    }
+
+    #[test]
+    fn test_serialize_crlf() {
+        let input = "    [D]    \r\n[N] [C]    \r\n[Z] [M] [P]\r\n 1   2   3 \r\n\r\nmove 1 from 2 to 1\r\nmove 3 from 1 to 3\r\n";
+        let (description, moves) = parse_input(input);
+        let mut board = Board::parse(&description);
+        assert_eq!(board.labels(), vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+        assert_eq!(board.run(&mut crane::CraneMover9000, &moves, false), vec![]);
+        assert_eq!(board.labels(), vec![vec![], vec!['M', 'C'], vec!['P', 'D', 'N', 'Z']]);
+        board.goto(0);
+        assert_eq!(board.serialize(), input);
+    }
+
+    #[test]
+    fn test_drawing_two_digit_labels() {
+        let mut stacks = vec![vec![]; 11];
+        stacks[0] = vec!['A'];
+        stacks[9] = vec!['B', 'C'];
+        stacks[10] = vec!['D'];
+        let drawing = Board::new(stacks).drawing();
+        assert_eq!(drawing, "                                    [C]    
+[A]                                 [B] [D]
+ 1   2   3   4   5   6   7   8   9  10  11 
+");
+        let mut lines: Vec<&str> = drawing.lines().collect();
+        lines.reverse();
+        assert_eq!(Board::parse(&lines).drawing(), drawing);
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let input = "[A]    
+ 1   2 
+
+move 1 from 1 to 2
+move 2 from 2 to 1
+move 1 from 2 to 3
+move one from 2 to 1
+
+move 1 from 2 to 1
+";
+        let (description, moves) = parse_input(input);
+        let mut board = Board::parse(&description);
+        let errors = board.run(&mut crane::CraneMover9001, &moves, true);
+        assert_eq!(errors, vec![
+            MoveError { line: 5, kind: MoveErrorKind::TooFewCrates { stack: 2, height: 1, count: 2 } },
+            MoveError { line: 6, kind: MoveErrorKind::NoSuchStack { stack: 3, stacks: 2 } },
+            MoveError { line: 7, kind: MoveErrorKind::Malformed { text: "move one from 2 to 1".to_string() } },
+        ]);
+        assert_eq!(errors[0].to_string(), "line 5: cannot take 2 crates from stack 2, which holds 1");
+        assert_eq!(board.labels(), vec![vec!['A'], vec![]]);
+        assert_eq!(board.step(), 2);
+
+        let mut board = Board::parse(&description);
+        assert_eq!(board.run(&mut crane::CraneMover9001, &moves, false).len(), 1);
+        assert_eq!(board.labels(), vec![vec![], vec!['A']]);
+    }
+
+    #[test]
+    fn test_serialize_label_in_first_column() {
+        let input = "[A] [B]
+1   2
+
+move 1 from 1 to 2
+";
+        let (description, moves) = parse_input(input);
+        let mut board = Board::parse(&description);
+        assert_eq!(board.run(&mut crane::CraneMover9000, &moves, false), vec![]);
+        assert_eq!(board.drawing(), "    [A]
+    [B]
+1   2
+");
+        board.goto(0);
+        assert_eq!(board.serialize(), input);
+
+        let mut board = Board::parse(&drawing("[A]
+1   2"));
+        board.apply(&mut crane::CraneMover9000, 1, 1, 2).unwrap();
+        assert_eq!(board.drawing(), "   [A]
+1   2
+");
+    }
+
+    #[test]
+    fn test_label_columns() {
+        assert_eq!(label_columns(" 1   2  10 "), vec![(1, 1), (5, 5), (8, 9)]);
+        assert_eq!(nearest_label(&label_columns(" 1   2  10 "), 9), 2);
+        assert_eq!(nearest_label(&label_columns(" 1   2  10 "), 6), 1);
+    }
 }
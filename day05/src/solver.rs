@@ -0,0 +1,149 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{Board, Crane, Crate};
+
+type Stacks = Vec<Vec<char>>;
+type Move = (usize, usize, usize);
+
+/// Gives up after looking at this many layouts, as the search space grows
+/// exponentially with the number of moves needed.
+pub const MAX_STATES: usize = 2_000_000;
+
+/// Finds a shortest list of `(count, from, to)` moves that turns `start`
+/// into `target` with `crane`, by breadth-first search over layouts.
+/// The crane must not keep state between moves.
+pub fn solve(start: &Board, target: &Board, crane: &mut dyn Crane) -> Result<Vec<Move>, String> {
+    let (start_layout, target_layout) = (encode(start), encode(target));
+    let (start, target) = (start.labels(), target.labels());
+    if start.len() != target.len() {
+        return Err(format!("start has {} stacks but target has {}", start.len(), target.len()));
+    }
+    let sorted = |s: &Stacks| {
+        let mut crates: Vec<char> = s.iter().flatten().copied().collect();
+        crates.sort();
+        crates
+    };
+    if sorted(&start) != sorted(&target) {
+        return Err("start and target do not have the same crates".to_string());
+    }
+
+    // layouts are stored as one string each; `parents[i]` is the layout and
+    // move that first led to layout `i`
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut parents: Vec<Option<(usize, Move)>> = vec![None];
+    let mut queue: VecDeque<(String, usize)> = VecDeque::new();
+    seen.insert(start_layout.clone(), 0);
+    queue.push_back((start_layout, 0));
+
+    // one board for the whole search: it is loaded with each layout and
+    // the two touched stacks are put back after every move
+    let mut board = Board::new(vec![vec![]; start.len()]);
+    while let Some((layout, index)) = queue.pop_front() {
+        if layout == target_layout {
+            return Ok(path_to(&parents, index));
+        }
+
+        let stacks = decode(&layout);
+        for (i, s) in stacks.iter().enumerate() {
+            board.stacks[i] = crates(s, i + 1);
+        }
+        for from in 1..=stacks.len() {
+            for to in (1..=stacks.len()).filter(|&to| to != from) {
+                for count in 1..=stacks[from-1].len() {
+                    crane.move_items(&mut board, count, from, to).expect("count is at most the stack height");
+                    let next = encode(&board);
+                    for stack in [from, to] {
+                        board.stacks[stack-1] = crates(&stacks[stack-1], stack);
+                    }
+                    if !seen.contains_key(&next) {
+                        if seen.len() >= MAX_STATES {
+                            return Err(format!("no solution within {} layouts", MAX_STATES));
+                        }
+                        seen.insert(next.clone(), parents.len());
+                        queue.push_back((next, parents.len()));
+                        parents.push(Some((index, (count, from, to))));
+                    }
+                }
+            }
+        }
+    }
+
+    Err("target cannot be reached".to_string())
+}
+
+/// The crate labels with stacks separated by newlines, which cannot be
+/// labels themselves.
+fn encode(board: &Board) -> String {
+    let mut layout = String::new();
+    for (i, s) in board.stacks.iter().enumerate() {
+        if i > 0 {
+            layout.push('\n');
+        }
+        layout.extend(s.iter().map(|c| c.label));
+    }
+    layout
+}
+
+fn decode(layout: &str) -> Stacks {
+    layout.split('\n').map(|s| s.chars().collect()).collect()
+}
+
+fn crates(labels: &[char], origin: usize) -> Vec<Crate> {
+    labels.iter().map(|&label| Crate { label, origin, trail: vec![] }).collect()
+}
+
+fn path_to(parents: &[Option<(usize, Move)>], mut index: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some((previous, m)) = parents[index] {
+        moves.push(m);
+        index = previous;
+    }
+    moves.reverse();
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crane::{CraneMover9000, CraneMover9001};
+
+    fn apply(start: &Board, moves: &[(usize, usize, usize)], crane: &mut dyn Crane) -> Stacks {
+        let mut board = Board::new(start.labels());
+        for &(count, from, to) in moves {
+            crane.move_items(&mut board, count, from, to).unwrap();
+        }
+        board.labels()
+    }
+
+    #[test]
+    fn test_solve_sample() {
+        let start = Board::new(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+
+        let target = Board::new(vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']]);
+        let moves = solve(&start, &target, &mut CraneMover9000).unwrap();
+        assert!(moves.len() <= 4);
+        assert_eq!(apply(&start, &moves, &mut CraneMover9000), target.labels());
+
+        let target = Board::new(vec![vec!['M'], vec!['C'], vec!['P', 'Z', 'N', 'D']]);
+        let moves = solve(&start, &target, &mut CraneMover9001).unwrap();
+        assert!(moves.len() <= 4);
+        assert_eq!(apply(&start, &moves, &mut CraneMover9001), target.labels());
+    }
+
+    #[test]
+    fn test_solve_shortest() {
+        let start = Board::new(vec![vec!['A', 'B', 'C'], vec![]]);
+        assert_eq!(solve(&start, &start, &mut CraneMover9000), Ok(vec![]));
+
+        let reversed = Board::new(vec![vec![], vec!['C', 'B', 'A']]);
+        assert_eq!(solve(&start, &reversed, &mut CraneMover9000), Ok(vec![(3, 1, 2)]));
+        assert_eq!(solve(&start, &reversed, &mut CraneMover9001).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_solve_mismatch() {
+        let start = Board::new(vec![vec!['A'], vec![]]);
+        assert!(solve(&start, &Board::new(vec![vec!['B'], vec![]]), &mut CraneMover9000).is_err());
+        assert!(solve(&start, &Board::new(vec![vec!['A']]), &mut CraneMover9000).is_err());
+    }
+}
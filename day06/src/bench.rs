@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::first_marker;
+
+/// Times the counting window against the earlier `VecDeque` scan on
+/// generated streams of up to `size` symbols. Build with `--release` for
+/// useful numbers.
+pub fn run(size: usize) {
+    let letters = random_letters(size);
+    for len in [4, 14] {
+        compare("random letters", &letters, len);
+    }
+    // the old scan is quadratic in the window length, so keep its run short
+    for len in [14, 64, 256, 1024] {
+        let size = size.min(4_000_000_000 / (len * len)).max(2 * len);
+        assert_eq!(compare("late marker", &late_marker(size, len), len), Some(size - 1));
+    }
+}
+
+fn compare(name: &str, data: &str, len: usize) -> Option<usize> {
+    let (deque, deque_time) = time(|| deque_marker_pos(black_box(data), len));
+    let (window, window_time) = time(|| first_marker(black_box(data), len));
+    assert_eq!(deque, window);
+
+    println!("{:<14} {:>7} symbols, window {:>4}: VecDeque {:>9.1} ms, counts {:>7.1} ms, speed-up {:.1}x",
+        name,
+        data.chars().count(),
+        len,
+        deque_time.as_secs_f64() * 1000.0,
+        window_time.as_secs_f64() * 1000.0,
+        deque_time.as_secs_f64() / window_time.as_secs_f64());
+    window
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = black_box(f());
+    (result, start.elapsed())
+}
+
+/// Random letters from a fixed-seed xorshift generator, so short markers
+/// are found early.
+fn random_letters(size: usize) -> String {
+    let mut state: u64 = 0x2022_1206;
+    (0..size).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (b'a' + (state % 26) as u8) as char
+    }).collect()
+}
+
+/// A cycle of `len - 1` distinct symbols, so every window holds exactly one
+/// repeat at its far ends, followed by one new symbol that completes the
+/// only marker at the very end.
+fn late_marker(size: usize, len: usize) -> String {
+    let symbol = |i: usize| char::from_u32(0x4e00 + i as u32).expect("CJK ideograph");
+    let mut data: String = (0..size - 1).map(|i| symbol(i % (len - 1))).collect();
+    data.push(symbol(len));
+    data
+}
+
+/// The earlier implementation, returning `None` instead of panicking.
+fn deque_marker_pos(line: &str, sequence_length: usize) -> Option<usize> {
+    let mut deque: VecDeque<char> = VecDeque::with_capacity(sequence_length);
+    for (i, c) in line.chars().enumerate() {
+        deque.push_back(c);
+        deque.make_contiguous();
+
+        if deque.len() >= sequence_length {
+            if all_distinct(&deque) {
+                return Some(i);
+            }
+            deque.pop_front();
+        }
+    }
+    None
+}
+
+fn all_distinct(v: &VecDeque<char>) -> bool {
+    let (s, _) = v.as_slices();
+    for i in 1..s.len() {
+        if s[0..i].contains(&s[i]) {
+            return false;
+        }
+    }
+    true
+}
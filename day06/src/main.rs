@@ -1,40 +1,158 @@
-use std::{env, fs};
-use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::fs::File;
+use std::{env, io, process};
+
+mod bench;
+mod stream;
 
 fn main() {
-    let input_path = env::args().skip(1).next().expect("give input file");
-    let contents = fs::read_to_string(input_path).expect("no read file");
-    for line in contents.lines() {
-        let short_marker_pos = find_marker_pos(&line, 4);
-        println!("4 marker at {}", short_marker_pos + 1);
+    let mut args: Vec<String> = env::args().collect();
+    let chunk_size: usize = take_flag(&mut args, "--chunk")
+        .map(|n| n.parse().expect("--chunk needs a number"))
+        .unwrap_or(64 * 1024);
+    assert!(chunk_size > 0, "chunk size must be at least 1");
+    let mode = match take_flag(&mut args, "--mode").as_deref() {
+        None | Some("first") => stream::Mode::First,
+        Some("all") => stream::Mode::All,
+        Some("non-overlapping") => stream::Mode::NonOverlapping,
+        Some(other) => {
+            eprintln!("unknown mode {:?}, use first, all or non-overlapping", other);
+            process::exit(1);
+        }
+    };
+    if args.iter().any(|a| a == "--mode") {
+        eprintln!("--mode can only be given once");
+        process::exit(1);
+    }
+    if args.get(1).map(String::as_str) == Some("bench") {
+        let size: usize = args.get(2).map(|n| n.parse().expect("size must be a number")).unwrap_or(200_000);
+        bench::run(size);
+        return;
+    }
+
+    let input_path = args.get(1).expect("give input file");
+    let mut lengths: Vec<usize> = args[2..].iter()
+        .map(|n| n.parse().ok().filter(|&len| len > 0).unwrap_or_else(|| {
+            eprintln!("window lengths must be positive numbers, got {:?}", n);
+            process::exit(1);
+        }))
+        .collect();
+    if lengths.is_empty() {
+        lengths = vec![4, 14];
+    }
 
-        let long_marker_pos = find_marker_pos(&line, 14);
-        println!("14 marker at {}", long_marker_pos + 1);
+    let report = |m: stream::Marker| println!("{}", m);
+    let result = if input_path == "-" {
+        stream::scan(io::stdin().lock(), &lengths, mode, chunk_size, report)
+    } else {
+        stream::scan(File::open(input_path).expect("no read file"), &lengths, mode, chunk_size, report)
+    };
+    if let Err(e) = result {
+        eprintln!("cannot read input: {}", e);
+        process::exit(1);
     }
 }
 
-fn find_marker_pos(line: &str, sequence_length: usize) -> u32 {
-    let mut deque: VecDeque<char> = VecDeque::with_capacity(sequence_length);
-    for (i, c) in line.chars().enumerate() {
-        deque.push_back(c);
-        deque.make_contiguous();
+/// Removes `name` and the value following it from `args`.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    args.remove(i);
+    if i < args.len() {
+        Some(args.remove(i))
+    } else {
+        panic!("{} needs a value", name)
+    }
+}
+
+/// The last `len` symbols of a stream, kept in a ring buffer with a count
+/// per symbol and the number of symbols seen more than once, so that each
+/// step is O(1) however long the window is.
+struct Window {
+    ring: Vec<char>,
+    counts: HashMap<char, usize>,
+    duplicates: usize,
+    seen: usize,
+}
+
+impl Window {
+    fn new(len: usize) -> Window {
+        assert!(len > 0, "window length must be at least 1");
+        Window { ring: vec!['\0'; len], counts: HashMap::new(), duplicates: 0, seen: 0 }
+    }
 
-        if deque.len() >= sequence_length {
-            if all_distinct(&deque) {
-                return i as u32
+    /// Adds the next symbol and tells whether the window is now full of
+    /// distinct symbols.
+    fn push(&mut self, c: char) -> bool {
+        let len = self.ring.len();
+        let slot = self.seen % len;
+        if self.seen >= len {
+            let old = self.ring[slot];
+            let count = self.counts.get_mut(&old).expect("symbol in window is counted");
+            if *count == 2 {
+                self.duplicates -= 1;
             }
-            deque.pop_front();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&old);
+            }
+        }
+
+        self.ring[slot] = c;
+        let count = self.counts.entry(c).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            self.duplicates += 1;
         }
+        self.seen += 1;
+        self.seen >= len && self.duplicates == 0
     }
-    panic!("No starting sequence");
 }
 
-fn all_distinct(v: &VecDeque<char>) -> bool {
-    let (s, _) = v.as_slices();
-    for i in 1..s.len() {
-        if s[0..i].contains(&s[i]) {
-            return false;
+/// Index of the last symbol of the first run of `len` distinct symbols.
+fn first_marker(data: &str, len: usize) -> Option<usize> {
+    let mut window = Window::new(len);
+    data.chars().position(|c| window.push(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: [(&str, usize, usize); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn test_samples() {
+        for (line, short, long) in SAMPLES {
+            assert_eq!(first_marker(line, 4), Some(short - 1));
+            assert_eq!(first_marker(line, 14), Some(long - 1));
         }
     }
-    true
+
+    #[test]
+    fn test_long_windows() {
+        let symbol = |i: u32| char::from_u32(0x4e00 + i).unwrap();
+        let data: String = (0..20_000).map(|i| symbol(i % 2999)).collect();
+        assert_eq!(first_marker(&data, 2999), Some(2998));
+        assert_eq!(first_marker(&data, 3000), None);
+
+        let mut data = "a".repeat(5000);
+        data.extend((0..5000).map(symbol));
+        // the last "a" and the first 4999 symbols already differ
+        assert_eq!(first_marker(&data, 5000), Some(9998));
+        assert_eq!(first_marker(&data, 5001), Some(9999));
+        assert_eq!(first_marker(&data, 1), Some(0));
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(first_marker("αβγδ", 4), Some(3));
+        assert_eq!(first_marker("ααβγδ", 4), Some(4));
+        assert_eq!(first_marker("αβγα", 4), None);
+    }
 }
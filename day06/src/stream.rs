@@ -0,0 +1,281 @@
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+
+use crate::Window;
+
+/// A marker in a datastream: the 1-based stream (input line) it is on, its
+/// length and the number of symbols read from that stream up to and
+/// including it. `end` is `None` when the stream ended without such a marker.
+#[derive(Debug, PartialEq)]
+pub struct Marker {
+    pub stream: usize,
+    pub len: usize,
+    pub end: Option<usize>,
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stream {}: {} marker at ", self.stream, self.len)?;
+        match self.end {
+            Some(end) => write!(f, "{}", end),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+/// Which markers of each length to report.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    First,
+    All,
+    NonOverlapping,
+}
+
+/// Decodes UTF-8 one byte at a time, so a symbol split across chunks is
+/// still seen once. Invalid sequences become U+FFFD, as in `from_utf8_lossy`.
+#[derive(Default)]
+struct Utf8 {
+    buffer: [u8; 4],
+    len: usize,
+    need: usize,
+}
+
+impl Utf8 {
+    /// Takes the next byte and returns the symbols it completes: an
+    /// unfinished sequence it cuts short, then the symbol it ends, if any.
+    fn push(&mut self, b: u8) -> [Option<char>; 2] {
+        let mut cut = None;
+        if self.len > 0 {
+            if b & 0xc0 == 0x80 {
+                self.buffer[self.len] = b;
+                self.len += 1;
+                if self.len < self.need {
+                    return [None, None];
+                }
+                let c = std::str::from_utf8(&self.buffer[..self.len]).ok().and_then(|s| s.chars().next());
+                self.len = 0;
+                return [Some(c.unwrap_or(char::REPLACEMENT_CHARACTER)), None];
+            }
+            cut = self.flush();
+        }
+
+        self.need = match b {
+            0x00..=0x7f => return [cut, Some(b as char)],
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => return [cut, Some(char::REPLACEMENT_CHARACTER)],
+        };
+        self.buffer[0] = b;
+        self.len = 1;
+        [cut, None]
+    }
+
+    /// Ends an unfinished sequence, if there is one.
+    fn flush(&mut self) -> Option<char> {
+        let unfinished = self.len > 0;
+        self.len = 0;
+        unfinished.then_some(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+/// Finds markers of each length in every newline-separated datastream, fed
+/// in chunks of any size.
+pub struct Detector {
+    lengths: Vec<usize>,
+    mode: Mode,
+    utf8: Utf8,
+    carriage_return: bool,
+    windows: Vec<Window>,
+    found: Vec<usize>,
+    stream: usize,
+    offset: usize,
+}
+
+impl Detector {
+    pub fn new(lengths: &[usize], mode: Mode) -> Detector {
+        Detector {
+            lengths: lengths.to_vec(),
+            mode,
+            utf8: Utf8::default(),
+            carriage_return: false,
+            windows: lengths.iter().map(|&len| Window::new(len)).collect(),
+            found: vec![0; lengths.len()],
+            stream: 1,
+            offset: 0,
+        }
+    }
+
+    /// Feeds the next bytes and calls `report` for each marker they complete.
+    pub fn feed(&mut self, chunk: &[u8], report: &mut impl FnMut(Marker)) {
+        for &b in chunk {
+            for c in self.utf8.push(b).into_iter().flatten() {
+                self.symbol(c, report);
+            }
+        }
+    }
+
+    /// Handles a decoded symbol. A `\r` is held back until the next symbol
+    /// shows whether it is part of a `\r\n` line ending.
+    fn symbol(&mut self, c: char, report: &mut impl FnMut(Marker)) {
+        if std::mem::take(&mut self.carriage_return) && c != '\n' {
+            self.data('\r', report);
+        }
+        match c {
+            '\n' => {
+                self.finish(report);
+                *self = Detector { stream: self.stream + 1, ..Detector::new(&self.lengths, self.mode) };
+            }
+            '\r' => self.carriage_return = true,
+            _ => self.data(c, report),
+        }
+    }
+
+    fn data(&mut self, c: char, report: &mut impl FnMut(Marker)) {
+        self.offset += 1;
+        for (i, window) in self.windows.iter_mut().enumerate() {
+            if !window.push(c) || (self.mode == Mode::First && self.found[i] > 0) {
+                continue;
+            }
+            self.found[i] += 1;
+            report(Marker { stream: self.stream, len: self.lengths[i], end: Some(self.offset) });
+            if self.mode == Mode::NonOverlapping {
+                // the next marker may not share symbols with this one
+                *window = Window::new(self.lengths[i]);
+            }
+        }
+    }
+
+    /// Reports the lengths without a marker in the current stream. An empty
+    /// stream, such as the one after a trailing newline, is skipped.
+    pub fn finish(&mut self, report: &mut impl FnMut(Marker)) {
+        if let Some(c) = self.utf8.flush() {
+            self.symbol(c, report);
+        }
+        if std::mem::take(&mut self.carriage_return) {
+            self.data('\r', report);
+        }
+        if self.offset == 0 {
+            return;
+        }
+        for (&len, &found) in self.lengths.iter().zip(&self.found) {
+            if found == 0 {
+                report(Marker { stream: self.stream, len, end: None });
+            }
+        }
+    }
+}
+
+/// Reads `reader` `chunk_size` bytes at a time and reports markers as soon
+/// as they are complete, so the input never has to fit in memory.
+pub fn scan(mut reader: impl Read, lengths: &[usize], mode: Mode, chunk_size: usize, mut report: impl FnMut(Marker)) -> io::Result<()> {
+    let mut detector = Detector::new(lengths, mode);
+    let mut buffer = vec![0; chunk_size];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => {
+                detector.finish(&mut report);
+                return Ok(());
+            }
+            Ok(n) => detector.feed(&buffer[..n], &mut report),
+            Err(e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers(input: &[u8], chunk_size: usize) -> Vec<Marker> {
+        scan_with(input, &[4, 14], Mode::First, chunk_size)
+    }
+
+    fn scan_with(input: &[u8], lengths: &[usize], mode: Mode, chunk_size: usize) -> Vec<Marker> {
+        let mut found = Vec::new();
+        scan(input, lengths, mode, chunk_size, |m| found.push(m)).unwrap();
+        found
+    }
+
+    fn ends(markers: &[Marker]) -> Vec<Option<usize>> {
+        markers.iter().map(|m| m.end).collect()
+    }
+
+    #[test]
+    fn test_chunk_boundaries() {
+        let input = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb\nbvwbjplbgvbhsrlpgdmjqwftvncz\naaaa\nnppdvjthqldpwncqszvftbrmjlhg\n";
+        let expected = vec![
+            Marker { stream: 1, len: 4, end: Some(7) },
+            Marker { stream: 1, len: 14, end: Some(19) },
+            Marker { stream: 2, len: 4, end: Some(5) },
+            Marker { stream: 2, len: 14, end: Some(23) },
+            Marker { stream: 3, len: 4, end: None },
+            Marker { stream: 3, len: 14, end: None },
+            Marker { stream: 4, len: 4, end: Some(6) },
+            Marker { stream: 4, len: 14, end: Some(23) },
+        ];
+        for chunk_size in [1, 2, 3, 7, 64, 4096] {
+            assert_eq!(markers(input, chunk_size), expected, "chunk size {}", chunk_size);
+        }
+
+        // CRLF line endings, with the pair split across chunks
+        let crlf: Vec<u8> = String::from_utf8(input.to_vec()).unwrap().replace('\n', "\r\n").into_bytes();
+        for chunk_size in [1, 2, 3, 7, 31, 64, 4096] {
+            assert_eq!(markers(&crlf, chunk_size), expected, "chunk size {}", chunk_size);
+        }
+        assert_eq!(ends(&scan_with(b"abc\r\n", &[4], Mode::First, 4)), vec![None]);
+        // a lone "\r" is data, as with `str::lines`
+        assert_eq!(ends(&scan_with(b"a\rbc\nabc\r", &[4], Mode::First, 1)), vec![Some(4), Some(4)]);
+    }
+
+    #[test]
+    fn test_offsets_beyond_first_chunk() {
+        let mut input = vec![b'z'; 10_000];
+        input.extend(b"abcdefghijklmn");
+        assert_eq!(markers(&input, 1000), vec![
+            Marker { stream: 1, len: 4, end: Some(10_003) },
+            Marker { stream: 1, len: 14, end: Some(10_013) },
+        ]);
+    }
+
+    #[test]
+    fn test_utf8_across_chunks() {
+        let input = "ααβγδεζηθικλμνξ\nαβγ\u{1f980}\n".as_bytes();
+        for chunk_size in 1..=5 {
+            assert_eq!(markers(input, chunk_size), vec![
+                Marker { stream: 1, len: 4, end: Some(5) },
+                Marker { stream: 1, len: 14, end: Some(15) },
+                Marker { stream: 2, len: 4, end: Some(4) },
+                Marker { stream: 2, len: 14, end: None },
+            ], "chunk size {}", chunk_size);
+        }
+
+        // a cut-off sequence counts as one replacement symbol
+        assert_eq!(ends(&scan_with(b"ab\xce", &[3], Mode::First, 1)), vec![Some(3)]);
+        assert_eq!(ends(&scan_with(b"a\xcebc", &[4], Mode::First, 2)), vec![Some(4)]);
+    }
+
+    #[test]
+    fn test_marker_display() {
+        let lines: Vec<String> = scan_with(b"abcab\naab\n", &[3], Mode::All, 4).iter().map(|m| m.to_string()).collect();
+        assert_eq!(lines, vec![
+            "stream 1: 3 marker at 3",
+            "stream 1: 3 marker at 4",
+            "stream 1: 3 marker at 5",
+            "stream 2: 3 marker at None",
+        ]);
+    }
+
+    #[test]
+    fn test_modes() {
+        let input = b"abcdabcaxyz";
+        assert_eq!(ends(&scan_with(input, &[3], Mode::First, 4)), vec![Some(3)]);
+        assert_eq!(ends(&scan_with(input, &[3], Mode::All, 4)),
+            (3..=11).map(Some).collect::<Vec<_>>());
+        assert_eq!(ends(&scan_with(input, &[3], Mode::NonOverlapping, 4)), vec![Some(3), Some(6), Some(9)]);
+        assert_eq!(ends(&scan_with(input, &[12], Mode::All, 4)), vec![None]);
+        assert_eq!(ends(&scan_with(b"aaaa", &[1, 2], Mode::NonOverlapping, 3)),
+            vec![Some(1), Some(2), Some(3), Some(4), None]);
+    }
+}